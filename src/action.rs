@@ -1,4 +1,9 @@
-use futures::{IntoFuture, Future};
+use std::time::Duration;
+
+use futures::{Async, IntoFuture, Future, Poll, Stream};
+use futures::future::{self, Either, FutureResult};
+
+use super::error::RetryError;
 
 /// An action can be run multiple times and produces a future.
 pub trait Action {
@@ -11,6 +16,29 @@ pub trait Action {
 
     /// Run this action, returning a future.
     fn run(&mut self) -> Self::Future;
+
+    /// Run this action with information about the current attempt, returning a future.
+    ///
+    /// By default the context is ignored.
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        let _ = context;
+        self.run()
+    }
+
+    /// Combines this action with another one, alternating between the two on every run.
+    ///
+    /// The first run uses this action, the second run uses `other`, and so on.
+    /// This is useful to fall back to a replica when retrying.
+    fn or_else<B>(self, other: B) -> OrElse<Self, B>
+        where Self: Sized,
+              B: Action<Item=Self::Item, Error=Self::Error>
+    {
+        OrElse {
+            first: self,
+            second: other,
+            use_second: false
+        }
+    }
 }
 
 impl<T: IntoFuture, F: FnMut() -> T> Action for F {
@@ -22,3 +50,421 @@ impl<T: IntoFuture, F: FnMut() -> T> Action for F {
         self().into_future()
     }
 }
+
+/// Information about the attempt that an action is being run for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunContext {
+    attempt: usize,
+    is_last: bool,
+    elapsed: Duration
+}
+
+impl RunContext {
+    pub(crate) fn new(attempt: usize, is_last: bool, elapsed: Duration) -> RunContext {
+        RunContext {
+            attempt,
+            is_last,
+            elapsed
+        }
+    }
+
+    /// The index of the current attempt, starting at zero for the initial attempt.
+    pub fn attempt(&self) -> usize {
+        self.attempt
+    }
+
+    /// Whether the retry strategy is exhausted, so that no further attempt will follow this one.
+    ///
+    /// With a total delay budget, the budget may still stop the retries after an attempt
+    /// that was not told it is the last one.
+    pub fn is_last(&self) -> bool {
+        self.is_last
+    }
+
+    /// The time that has elapsed since the first attempt was started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Action that receives a [`RunContext`](./struct.RunContext.html) each time it is run.
+///
+/// When run outside of a retry loop, the action is told that it is the initial attempt.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use futures::{Future, future};
+/// # use futures_backoff::{retry, RunContext, WithRunContext};
+/// #
+/// # fn main() {
+/// let future = retry(WithRunContext(|context: RunContext| {
+///     if context.is_last() {
+///         // use a more expensive fallback here...
+///     }
+///     future::ok::<u32, ::std::io::Error>(42)
+/// }));
+/// #
+/// # assert_eq!(future.wait().unwrap(), 42);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WithRunContext<F>(pub F);
+
+impl<T: IntoFuture, F: FnMut(RunContext) -> T> Action for WithRunContext<F> {
+    type Item = T::Item;
+    type Error = T::Error;
+    type Future = T::Future;
+
+    fn run(&mut self) -> Self::Future {
+        self.run_with_context(RunContext::new(0, false, Duration::from_secs(0)))
+    }
+
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        (self.0)(context).into_future()
+    }
+}
+
+/// Action that runs a fallible synchronous function, wrapping its result in a future.
+///
+/// Created by [`sync_fn`](./fn.sync_fn.html).
+#[derive(Debug)]
+pub struct SyncFn<F>(F);
+
+/// Creates an action from a fallible synchronous function.
+///
+/// The resulting action can be retried like any other, sleeping between attempts
+/// without blocking the executor.
+pub fn sync_fn<T, E, F: FnMut() -> Result<T, E>>(f: F) -> SyncFn<F> {
+    SyncFn(f)
+}
+
+impl<T, E, F: FnMut() -> Result<T, E>> Action for SyncFn<F> {
+    type Item = T;
+    type Error = E;
+    type Future = FutureResult<T, E>;
+
+    fn run(&mut self) -> Self::Future {
+        future::result((self.0)())
+    }
+}
+
+/// Action that runs a function producing a stream, and resolves with the first item of the stream.
+///
+/// Created by [`Strategy::retry_stream_first`](./struct.Strategy.html#method.retry_stream_first).
+#[derive(Debug)]
+pub struct StreamFirst<F>(F);
+
+impl<F> StreamFirst<F> {
+    pub(crate) fn new(f: F) -> StreamFirst<F> {
+        StreamFirst(f)
+    }
+}
+
+impl<S: Stream, F: FnMut() -> S> Action for StreamFirst<F> {
+    type Item = S::Item;
+    type Error = RetryError<S::Error>;
+    type Future = FirstItem<S>;
+
+    fn run(&mut self) -> Self::Future {
+        FirstItem((self.0)())
+    }
+}
+
+/// Future that resolves with the first item of a stream.
+///
+/// Fails with [`RetryError::Empty`](./enum.RetryError.html#variant.Empty) if the stream
+/// ends without yielding an item.
+#[derive(Debug)]
+pub struct FirstItem<S>(S);
+
+impl<S: Stream> Future for FirstItem<S> {
+    type Item = S::Item;
+    type Error = RetryError<S::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.0.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(Some(item))) => Ok(Async::Ready(item)),
+            Ok(Async::Ready(None)) => Err(RetryError::Empty),
+            Err(err) => Err(RetryError::Operation(err))
+        }
+    }
+}
+
+/// Action that alternates between two actions on every run.
+///
+/// Created by [`Action::or_else`](./trait.Action.html#method.or_else).
+#[derive(Debug)]
+pub struct OrElse<A, B> {
+    first: A,
+    second: B,
+    use_second: bool
+}
+
+impl<A, B> Action for OrElse<A, B>
+    where A: Action,
+          B: Action<Item=A::Item, Error=A::Error>
+{
+    type Item = A::Item;
+    type Error = A::Error;
+    type Future = Either<A::Future, B::Future>;
+
+    fn run(&mut self) -> Self::Future {
+        let use_second = self.use_second;
+        self.use_second = !use_second;
+        if use_second {
+            Either::B(self.second.run())
+        } else {
+            Either::A(self.first.run())
+        }
+    }
+
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        let use_second = self.use_second;
+        self.use_second = !use_second;
+        if use_second {
+            Either::B(self.second.run_with_context(context))
+        } else {
+            Either::A(self.first.run_with_context(context))
+        }
+    }
+}
+
+impl<A, B> IdempotentAction for OrElse<A, B>
+    where A: IdempotentAction,
+          B: IdempotentAction<Item=A::Item, Error=A::Error>
+{}
+
+/// Marker for actions that are safe to run more than once.
+///
+/// Retrying an action that is not idempotent, such as one that charges a credit card,
+/// can repeat its side effects when an attempt fails after they took place.
+/// [`Strategy::retry_idempotent`](./struct.Strategy.html#method.retry_idempotent) only
+/// accepts actions that implement this trait, so that such mistakes are caught at compile time.
+///
+/// Closures don't implement this trait, they need to be declared idempotent explicitly
+/// by wrapping them in [`Idempotent`](./struct.Idempotent.html):
+///
+/// ```rust,compile_fail
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use futures::future;
+/// # use futures_backoff::Strategy;
+/// #
+/// # fn main() {
+/// Strategy::default().retry_idempotent(|| future::ok::<u32, ::std::io::Error>(42));
+/// # }
+/// ```
+pub trait IdempotentAction: Action {}
+
+/// Action that declares the wrapped action to be safe to run more than once.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use futures::{Future, future};
+/// # use futures_backoff::{Idempotent, Strategy};
+/// #
+/// # fn main() {
+/// let future = Strategy::default().retry_idempotent(Idempotent(|| {
+///     // fetch some resource here...
+///     future::ok::<u32, ::std::io::Error>(42)
+/// }));
+/// #
+/// # assert_eq!(future.wait().unwrap(), 42);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Idempotent<A>(pub A);
+
+impl<A: Action> Action for Idempotent<A> {
+    type Item = A::Item;
+    type Error = A::Error;
+    type Future = A::Future;
+
+    fn run(&mut self) -> Self::Future {
+        self.0.run()
+    }
+
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        self.0.run_with_context(context)
+    }
+}
+
+impl<A: Action> IdempotentAction for Idempotent<A> {}
+
+/// Action that runs a borrowed action.
+///
+/// Created by [`Strategy::retry_ref`](./struct.Strategy.html#method.retry_ref).
+#[derive(Debug)]
+pub struct ByRef<'a, A: 'a>(&'a mut A);
+
+impl<'a, A> ByRef<'a, A> {
+    pub(crate) fn new(action: &'a mut A) -> ByRef<'a, A> {
+        ByRef(action)
+    }
+}
+
+impl<'a, A: Action> Action for ByRef<'a, A> {
+    type Item = A::Item;
+    type Error = A::Error;
+    type Future = A::Future;
+
+    fn run(&mut self) -> Self::Future {
+        self.0.run()
+    }
+
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        self.0.run_with_context(context)
+    }
+}
+
+/// Action that passes a mutable context to every run.
+pub(crate) struct ContextAction<F, Ctx> {
+    f: F,
+    context: Option<Ctx>
+}
+
+impl<F, Ctx> ContextAction<F, Ctx> {
+    pub fn new(f: F, context: Ctx) -> ContextAction<F, Ctx> {
+        ContextAction {
+            f,
+            context: Some(context)
+        }
+    }
+
+    pub fn take_context(&mut self) -> Ctx {
+        self.context.take().expect("context has already been taken")
+    }
+}
+
+impl<T: IntoFuture, F: FnMut(&mut Ctx) -> T, Ctx> Action for ContextAction<F, Ctx> {
+    type Item = T::Item;
+    type Error = T::Error;
+    type Future = T::Future;
+
+    fn run(&mut self) -> Self::Future {
+        let context = self.context.as_mut().expect("context has already been taken");
+        (self.f)(context).into_future()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::time::Duration;
+    use futures::Future;
+    use futures::future::{self, FutureResult};
+    use futures::stream;
+    use super::{Action, Idempotent};
+    use super::super::error::RetryError;
+    use super::super::Strategy;
+
+    #[test]
+    fn or_else_alternates_between_actions() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let first_calls = Cell::new(0);
+        let second_calls = Cell::new(0);
+        let res = {
+            let first = || {
+                first_calls.set(first_calls.get() + 1);
+                Err::<&str, u64>(42)
+            };
+            let second = || {
+                second_calls.set(second_calls.get() + 1);
+                Ok::<&str, u64>("second")
+            };
+            s.retry(first.or_else(second)).wait()
+        };
+
+        assert_eq!(res, Ok("second"));
+        assert_eq!(first_calls.get(), 1);
+        assert_eq!(second_calls.get(), 1);
+    }
+
+    #[test]
+    fn retry_idempotent_accepts_wrapped_actions() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let calls = Cell::new(0);
+        let res = s.retry_idempotent(Idempotent(|| {
+            calls.set(calls.get() + 1);
+            if calls.get() < 3 {
+                Err::<&str, u64>(42)
+            } else {
+                Ok::<&str, u64>("done")
+            }
+        })).wait();
+
+        assert_eq!(res, Ok("done"));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn or_else_of_idempotent_actions_is_idempotent() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let first = Idempotent(|| Err::<&str, u64>(42));
+        let second = Idempotent(|| Ok::<&str, u64>("second"));
+
+        assert_eq!(s.retry_idempotent(first.or_else(second)).wait(), Ok("second"));
+    }
+
+    struct Flaky {
+        calls: usize
+    }
+
+    impl Action for Flaky {
+        type Item = usize;
+        type Error = usize;
+        type Future = FutureResult<usize, usize>;
+
+        fn run(&mut self) -> Self::Future {
+            self.calls += 1;
+            if self.calls % 3 == 0 {
+                future::ok(self.calls)
+            } else {
+                future::err(self.calls)
+            }
+        }
+    }
+
+    #[test]
+    fn retry_ref_leaves_the_action_usable() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let mut action = Flaky { calls: 0 };
+
+        assert_eq!(s.retry_ref(&mut action).wait(), Ok(3));
+        assert_eq!(action.calls, 3);
+        assert_eq!(s.retry_ref(&mut action).wait(), Ok(6));
+        assert_eq!(action.run().wait(), Err(7));
+    }
+
+    #[test]
+    fn retry_stream_first_retries_empty_streams() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let mut num_calls = 0;
+        let res = s.retry_stream_first(|| {
+            num_calls += 1;
+            let items = if num_calls < 3 { vec![] } else { vec![Ok(num_calls), Ok(42)] };
+            stream::iter_result::<_, usize, u64>(items)
+        }).wait();
+
+        assert_eq!(res, Ok(3));
+        assert_eq!(num_calls, 3);
+    }
+
+    #[test]
+    fn retry_stream_first_retries_failing_streams() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let res = s.retry_stream_first(|| {
+            stream::iter_result::<_, usize, u64>(vec![Err(42), Ok(1)])
+        }).wait();
+
+        assert_eq!(res, Err(RetryError::Operation(42)));
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::mem;
+use std::vec;
+
+use futures::{Future, Poll};
+
+use super::action::Action;
+use super::driver::{SleepDriver, TimerDriver};
+use super::future::Retry;
+use super::strategy::{Strategy, StrategyIter};
+
+/// Future that retries a sequence of actions, one after the other, until one succeeds.
+///
+/// Each action is retried according to the strategy before moving on to the next one.
+/// If all actions fail, the future resolves with the errors of all actions, in order.
+///
+/// Created by [`retry_any`](./fn.retry_any.html).
+pub struct RetryAny<A, D = TimerDriver> where A: Action, D: SleepDriver {
+    pending: vec::IntoIter<(A, StrategyIter)>,
+    current: Option<Retry<A, D>>,
+    driver: D,
+    errors: Vec<A::Error>
+}
+
+impl<A: Action> RetryAny<A> {
+    /// Creates a new retry future.
+    pub fn new<I>(strategy: &Strategy, actions: I) -> RetryAny<A>
+        where I: IntoIterator<Item=A>
+    {
+        RetryAny::new_with_driver(TimerDriver::default(), strategy, actions)
+    }
+}
+
+impl<A: Action, D: SleepDriver + Clone> RetryAny<A, D> {
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver<I>(driver: D, strategy: &Strategy, actions: I) -> RetryAny<A, D>
+        where I: IntoIterator<Item=A>
+    {
+        let pending = actions.into_iter()
+            .map(|action| (action, strategy.iter()))
+            .collect::<Vec<_>>();
+        RetryAny {
+            pending: pending.into_iter(),
+            current: None,
+            driver,
+            errors: Vec::new()
+        }
+    }
+}
+
+impl<A: Action, D: SleepDriver> fmt::Debug for RetryAny<A, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryAny").finish()
+    }
+}
+
+impl<A: Action, D: SleepDriver + Clone> Future for RetryAny<A, D> {
+    type Item = A::Item;
+    type Error = Vec<A::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let result = match self.current {
+                Some(ref mut retry) => retry.poll(),
+                None => match self.pending.next() {
+                    Some((action, strategy_iter)) => {
+                        self.current = Some(Retry::with_strategy_iter(self.driver.clone(), strategy_iter, action));
+                        continue;
+                    },
+                    None => return Err(mem::take(&mut self.errors))
+                }
+            };
+            match result {
+                Ok(async) => return Ok(async),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.current = None;
+                }
+            }
+        }
+    }
+}
+
+/// Run the given actions one after the other, and use the given strategy to retry each of them.
+///
+/// Resolves with the first success, or with the errors of all actions if all of them fail.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::time::Duration;
+/// # use futures::{Future, future};
+/// # use futures_backoff::{retry_any, Strategy};
+/// #
+/// # fn main() {
+/// let strategy = Strategy::fixed(Duration::from_millis(10))
+///     .with_max_retries(2);
+///
+/// let mirrors = vec!["primary", "secondary"];
+/// let future = retry_any(mirrors.into_iter().map(|mirror| move || {
+///     // fetch from the mirror here...
+///     future::ok::<&str, ::std::io::Error>(mirror)
+/// }), &strategy);
+/// #
+/// # assert_eq!(future.wait().unwrap(), "primary");
+/// # }
+/// ```
+pub fn retry_any<I>(actions: I, strategy: &Strategy) -> RetryAny<I::Item>
+    where I: IntoIterator,
+          I::Item: Action
+{
+    RetryAny::new(strategy, actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::time::Duration;
+    use futures::Future;
+    use super::{retry_any, Strategy};
+
+    #[test]
+    fn resolves_with_the_first_success() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let calls = vec![Cell::new(0), Cell::new(0), Cell::new(0)];
+        let res = {
+            let actions = calls.iter().enumerate().map(|(i, calls)| move || {
+                calls.set(calls.get() + 1);
+                if i < 2 {
+                    Err::<usize, usize>(i)
+                } else {
+                    Ok::<usize, usize>(i)
+                }
+            });
+            retry_any(actions, &s).wait()
+        };
+
+        assert_eq!(res, Ok(2));
+        assert_eq!(calls.iter().map(Cell::get).collect::<Vec<_>>(), vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn collects_all_errors() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let actions = (0..3).map(|i| move || Err::<(), usize>(i));
+
+        assert_eq!(retry_any(actions, &s).wait(), Err(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn fails_without_actions() {
+        let s = Strategy::default();
+        let actions: Vec<fn() -> Result<(), usize>> = Vec::new();
+
+        assert_eq!(retry_any(actions, &s).wait(), Err(vec![]));
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+
+use futures::{Async, Future, Poll, Stream};
+use futures::stream::BufferUnordered;
+
+use super::action::Action;
+use super::driver::{SleepDriver, TimerDriver};
+use super::future::Retry;
+use super::strategy::{Strategy, StrategyIter};
+
+/// Future that resolves with the result of a retry future, without failing.
+struct Settle<A: Action, D: SleepDriver>(Retry<A, D>);
+
+impl<A: Action, D: SleepDriver> Future for Settle<A, D> {
+    type Item = Result<A::Item, A::Error>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, ()> {
+        match self.0.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(item)) => Ok(Async::Ready(Ok(item))),
+            Err(err) => Ok(Async::Ready(Err(err)))
+        }
+    }
+}
+
+/// Stream of retry futures, started lazily for each action.
+struct Pending<I, D> {
+    actions: I,
+    strategy_iter: StrategyIter,
+    driver: D
+}
+
+impl<I: Iterator, D: SleepDriver + Clone> Stream for Pending<I, D> where I::Item: Action {
+    type Item = Settle<I::Item, D>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, ()> {
+        Ok(Async::Ready(self.actions.next().map(|action| {
+            Settle(Retry::with_strategy_iter(self.driver.clone(), self.strategy_iter.clone(), action))
+        })))
+    }
+}
+
+/// Stream that retries many actions, running a limited number of them at once.
+///
+/// Yields the result of each action, in the order in which they complete.
+/// The stream itself never fails.
+///
+/// Created by [`Strategy::retry_buffered`](./struct.Strategy.html#method.retry_buffered).
+pub struct RetryBuffered<I, D = TimerDriver> where I: Iterator, I::Item: Action, D: SleepDriver + Clone {
+    inner: BufferUnordered<Pending<I, D>>
+}
+
+impl<I: Iterator> RetryBuffered<I> where I::Item: Action {
+    /// Creates a new stream, running up to `concurrency` actions at once.
+    ///
+    /// Actions are taken from the iterator only once there is room to run them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is zero.
+    pub fn new<T>(strategy: &Strategy, actions: T, concurrency: usize) -> RetryBuffered<I>
+        where T: IntoIterator<Item=I::Item, IntoIter=I>
+    {
+        RetryBuffered::new_with_driver(TimerDriver::default(), strategy, actions, concurrency)
+    }
+}
+
+impl<I: Iterator, D: SleepDriver + Clone> RetryBuffered<I, D> where I::Item: Action {
+    /// Creates a new stream, using the provided `driver` to sleep between attempts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is zero.
+    pub fn new_with_driver<T>(driver: D, strategy: &Strategy, actions: T, concurrency: usize) -> RetryBuffered<I, D>
+        where T: IntoIterator<Item=I::Item, IntoIter=I>
+    {
+        assert!(concurrency > 0, "concurrency must be greater than zero");
+        let pending = Pending {
+            actions: actions.into_iter(),
+            strategy_iter: strategy.iter(),
+            driver
+        };
+        RetryBuffered {
+            inner: pending.buffer_unordered(concurrency)
+        }
+    }
+}
+
+impl<I: Iterator, D: SleepDriver + Clone> fmt::Debug for RetryBuffered<I, D> where I::Item: Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryBuffered").finish()
+    }
+}
+
+impl<I: Iterator, D: SleepDriver + Clone> Stream for RetryBuffered<I, D> where I::Item: Action {
+    type Item = Result<<I::Item as Action>::Item, <I::Item as Action>::Error>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, ()> {
+        self.inner.poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::time::Duration;
+    use futures::{Future, Stream};
+    use super::Strategy;
+
+    #[test]
+    fn yields_results_in_completion_order() {
+        let s = Strategy::fixed(Duration::from_millis(20))
+            .with_max_retries(2);
+        let failures = vec![0, 1, 0, 2, 0];
+        let calls = failures.iter().map(|_| Cell::new(0)).collect::<Vec<_>>();
+        let results = {
+            let actions = failures.iter().zip(calls.iter()).enumerate().map(|(i, (&failures, calls))| move || {
+                calls.set(calls.get() + 1);
+                if calls.get() <= failures {
+                    Err::<usize, usize>(i)
+                } else {
+                    Ok::<usize, usize>(i)
+                }
+            });
+            s.retry_buffered(actions, 2).collect().wait().unwrap()
+        };
+
+        assert_eq!(results, vec![Ok(0), Ok(2), Ok(1), Ok(4), Ok(3)]);
+        assert_eq!(calls.iter().map(Cell::get).collect::<Vec<_>>(), vec![1, 2, 1, 3, 1]);
+    }
+
+    #[test]
+    fn yields_errors_of_exhausted_actions() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let actions = (0..3).map(|i| move || if i == 1 { Err::<usize, usize>(i) } else { Ok(i) });
+        let mut results = s.retry_buffered(actions, 3).collect().wait().unwrap();
+        results.sort();
+
+        assert_eq!(results, vec![Ok(0), Ok(2), Err(1)]);
+    }
+
+    #[test]
+    fn takes_actions_lazily() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let actions = (0..).map(|i| move || Ok::<usize, ()>(i));
+        let results = s.retry_buffered(actions, 2).take(3).collect().wait().unwrap();
+
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "concurrency must be greater than zero")]
+    fn refuses_zero_concurrency() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        s.retry_buffered(vec![|| Ok::<(), ()>(())], 0);
+    }
+}
@@ -1,7 +1,69 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
 /// Specifies under which conditions a retry is attempted.
 pub trait Condition<E> {
     /// Determine whether to retry based on the previous error.
     fn should_retry(&mut self, error: &E) -> bool;
+
+    /// Determine whether to retry based on the previous error and the time
+    /// that has elapsed since the first attempt was started.
+    ///
+    /// By default the elapsed time is ignored.
+    fn should_retry_elapsed(&mut self, error: &E, elapsed: Duration) -> bool {
+        let _ = elapsed;
+        self.should_retry(error)
+    }
+
+    /// Determine whether to retry based on the previous error and the time that has
+    /// elapsed since the first attempt was started, possibly granting extra retries.
+    ///
+    /// By default no extra retries are granted.
+    fn decide(&mut self, error: &E, elapsed: Duration) -> RetryDecision {
+        RetryDecision::from(self.should_retry_elapsed(error, elapsed))
+    }
+
+    /// Combines this condition with another one, retrying only if both are satisfied.
+    ///
+    /// The other condition is only evaluated if this one is satisfied.
+    fn and<C: Condition<E>>(self, other: C) -> And<Self, C> where Self: Sized {
+        And(self, other)
+    }
+
+    /// Combines this condition with another one, retrying if either is satisfied.
+    ///
+    /// The other condition is only evaluated if this one is not satisfied.
+    fn or<C: Condition<E>>(self, other: C) -> Or<Self, C> where Self: Sized {
+        Or(self, other)
+    }
+
+    /// Negates this condition, retrying only if it is not satisfied.
+    fn not(self) -> Not<Self> where Self: Sized {
+        Not(self)
+    }
+}
+
+/// Evaluates the given condition for an error, without running a retry loop.
+///
+/// This is useful to check a condition in isolation, for example in tests.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures_backoff;
+/// # use std::io::{Error, ErrorKind};
+/// # use futures_backoff::is_retryable;
+/// #
+/// # fn main() {
+/// let mut condition = |err: &Error| err.kind() == ErrorKind::TimedOut;
+///
+/// assert!(is_retryable(&mut condition, &Error::from(ErrorKind::TimedOut)));
+/// assert!(!is_retryable(&mut condition, &Error::from(ErrorKind::NotFound)));
+/// # }
+/// ```
+pub fn is_retryable<E, C: Condition<E>>(condition: &mut C, error: &E) -> bool {
+    condition.should_retry(error)
 }
 
 impl<E, F: Fn(&E) -> bool> Condition<E> for F {
@@ -9,3 +71,292 @@ impl<E, F: Fn(&E) -> bool> Condition<E> for F {
         self(error)
     }
 }
+
+/// Decision about whether to retry after an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryDecision {
+    /// Whether to retry.
+    pub retry: bool,
+    /// The number of retries to add to the remaining retries of the strategy.
+    ///
+    /// Only takes effect if `retry` is set.
+    pub extra_retries: usize
+}
+
+impl From<bool> for RetryDecision {
+    fn from(retry: bool) -> RetryDecision {
+        RetryDecision {
+            retry,
+            extra_retries: 0
+        }
+    }
+}
+
+/// Condition that is satisfied if both conditions are satisfied.
+///
+/// Created by [`Condition::and`](./trait.Condition.html#method.and).
+#[derive(Debug)]
+pub struct And<C1, C2>(C1, C2);
+
+impl<E, C1: Condition<E>, C2: Condition<E>> Condition<E> for And<C1, C2> {
+    fn should_retry(&mut self, error: &E) -> bool {
+        self.0.should_retry(error) && self.1.should_retry(error)
+    }
+
+    fn should_retry_elapsed(&mut self, error: &E, elapsed: Duration) -> bool {
+        self.0.should_retry_elapsed(error, elapsed) && self.1.should_retry_elapsed(error, elapsed)
+    }
+
+    fn decide(&mut self, error: &E, elapsed: Duration) -> RetryDecision {
+        let first = self.0.decide(error, elapsed);
+        if !first.retry {
+            return first
+        }
+        let second = self.1.decide(error, elapsed);
+        RetryDecision {
+            retry: second.retry,
+            extra_retries: ::std::cmp::max(first.extra_retries, second.extra_retries)
+        }
+    }
+}
+
+/// Condition that is satisfied if either condition is satisfied.
+///
+/// Created by [`Condition::or`](./trait.Condition.html#method.or).
+#[derive(Debug)]
+pub struct Or<C1, C2>(C1, C2);
+
+impl<E, C1: Condition<E>, C2: Condition<E>> Condition<E> for Or<C1, C2> {
+    fn should_retry(&mut self, error: &E) -> bool {
+        self.0.should_retry(error) || self.1.should_retry(error)
+    }
+
+    fn should_retry_elapsed(&mut self, error: &E, elapsed: Duration) -> bool {
+        self.0.should_retry_elapsed(error, elapsed) || self.1.should_retry_elapsed(error, elapsed)
+    }
+
+    fn decide(&mut self, error: &E, elapsed: Duration) -> RetryDecision {
+        let first = self.0.decide(error, elapsed);
+        if first.retry {
+            return first
+        }
+        self.1.decide(error, elapsed)
+    }
+}
+
+/// Condition that is satisfied if the inner condition is not satisfied.
+///
+/// Created by [`Condition::not`](./trait.Condition.html#method.not).
+#[derive(Debug)]
+pub struct Not<C>(C);
+
+impl<E, C: Condition<E>> Condition<E> for Not<C> {
+    fn should_retry(&mut self, error: &E) -> bool {
+        !self.0.should_retry(error)
+    }
+
+    fn should_retry_elapsed(&mut self, error: &E, elapsed: Duration) -> bool {
+        !self.0.should_retry_elapsed(error, elapsed)
+    }
+}
+
+/// Condition that inspects both the previous error and the elapsed time.
+///
+/// Created by [`Strategy::retry_if_elapsed`](./struct.Strategy.html#method.retry_if_elapsed).
+/// When evaluated outside of a retry loop, the elapsed time is zero.
+#[derive(Debug)]
+pub struct Elapsed<F>(pub F);
+
+impl<E, F: FnMut(&E, Duration) -> bool> Condition<E> for Elapsed<F> {
+    fn should_retry(&mut self, error: &E) -> bool {
+        self.should_retry_elapsed(error, Duration::from_secs(0))
+    }
+
+    fn should_retry_elapsed(&mut self, error: &E, elapsed: Duration) -> bool {
+        (self.0)(error, elapsed)
+    }
+}
+
+/// Condition that decides whether to retry, and may grant extra retries for some errors.
+///
+/// Created by [`Strategy::retry_if_decision`](./struct.Strategy.html#method.retry_if_decision).
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::io::{Error, ErrorKind};
+/// # use futures::{Future, future};
+/// # use futures_backoff::{RetryDecision, Strategy};
+/// #
+/// # fn main() {
+/// let future = Strategy::default().retry_if_decision(|| {
+///     // do some real-world stuff here...
+///     future::ok(42)
+/// }, |err: &Error| match err.kind() {
+///     // be more patient when being rate limited
+///     ErrorKind::WouldBlock => RetryDecision { retry: true, extra_retries: 1 },
+///     ErrorKind::NotFound => RetryDecision { retry: false, extra_retries: 0 },
+///     _ => RetryDecision { retry: true, extra_retries: 0 }
+/// });
+/// #
+/// # assert_eq!(future.wait().unwrap(), 42);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Decision<F>(pub F);
+
+impl<E, F: FnMut(&E) -> RetryDecision> Condition<E> for Decision<F> {
+    fn should_retry(&mut self, error: &E) -> bool {
+        (self.0)(error).retry
+    }
+
+    fn decide(&mut self, error: &E, elapsed: Duration) -> RetryDecision {
+        let _ = elapsed;
+        (self.0)(error)
+    }
+}
+
+/// Condition that retries each category of errors up to an independent budget.
+///
+/// Every error is mapped to a category key by the given function. Retries are
+/// attempted until the budget of the error's category is used up. Errors in
+/// categories without a budget are not retried.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::io::{Error, ErrorKind};
+/// # use futures::{Future, future};
+/// # use futures_backoff::{retry_if, PerCategory};
+/// #
+/// # fn main() {
+/// let condition = PerCategory::new(|err: &Error| err.kind())
+///     .with_budget(ErrorKind::TimedOut, 3)
+///     .with_budget(ErrorKind::ConnectionReset, 10);
+///
+/// let future = retry_if(|| {
+///     // do some real-world stuff here...
+///     future::ok(42)
+/// }, condition);
+/// #
+/// # assert_eq!(future.wait().unwrap(), 42);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PerCategory<K, F> {
+    classify: F,
+    budgets: HashMap<K, usize>,
+    retries: HashMap<K, usize>
+}
+
+impl<K: Eq + Hash, F> PerCategory<K, F> {
+    /// Creates a new condition, using `classify` to map errors to categories.
+    pub fn new(classify: F) -> PerCategory<K, F> {
+        PerCategory {
+            classify,
+            budgets: HashMap::new(),
+            retries: HashMap::new()
+        }
+    }
+
+    /// Sets the maximum number of retry attempts for errors of the given category.
+    pub fn with_budget(mut self, category: K, retries: usize) -> Self {
+        self.budgets.insert(category, retries);
+        self
+    }
+}
+
+impl<E, K: Eq + Hash, F: FnMut(&E) -> K> Condition<E> for PerCategory<K, F> {
+    fn should_retry(&mut self, error: &E) -> bool {
+        let category = (self.classify)(error);
+        let budget = self.budgets.get(&category).cloned().unwrap_or(0);
+        let retries = self.retries.entry(category).or_insert(0);
+        if *retries < budget {
+            *retries += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use futures::Future;
+    use super::{is_retryable, Condition, PerCategory, RetryDecision};
+    use super::super::Strategy;
+
+    #[test]
+    fn or_retries_if_either_condition_is_satisfied() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(5);
+        let mut num_calls = 0;
+        let res = {
+            let action = || {
+                num_calls += 1;
+                Err::<(), u64>(num_calls)
+            };
+            let is_one = |e: &u64| *e == 1;
+            let is_two = |e: &u64| *e == 2;
+            s.retry_if(action, is_one.or(is_two)).wait()
+        };
+
+        assert_eq!(res, Err(3));
+        assert_eq!(num_calls, 3);
+    }
+
+    #[test]
+    fn and_and_not_combine_conditions() {
+        let is_large = |e: &u64| *e > 10;
+        let is_fatal = |e: &u64| *e == 20;
+        let mut condition = is_large.and(is_fatal.not());
+
+        assert!(condition.should_retry(&12));
+        assert!(!condition.should_retry(&20));
+        assert!(!condition.should_retry(&4));
+    }
+
+    #[test]
+    fn decision_can_extend_the_retries() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let mut num_calls = 0;
+        let res = {
+            let action = || {
+                num_calls += 1;
+                Err::<(), u64>(num_calls)
+            };
+            // Errors below 4 are rate limits, which grant another retry each.
+            s.retry_if_decision(action, |e: &u64| RetryDecision {
+                retry: true,
+                extra_retries: if *e < 4 { 1 } else { 0 }
+            }).wait()
+        };
+
+        assert_eq!(res, Err(6));
+        assert_eq!(num_calls, 6);
+    }
+
+    #[test]
+    fn is_retryable_evaluates_the_condition() {
+        let mut is_even = |e: &u64| e % 2 == 0;
+
+        assert!(is_retryable(&mut is_even, &2));
+        assert!(!is_retryable(&mut is_even, &3));
+    }
+
+    #[test]
+    fn is_retryable_updates_stateful_conditions() {
+        let mut condition = PerCategory::new(|e: &u64| *e)
+            .with_budget(1, 1);
+
+        assert!(is_retryable(&mut condition, &1));
+        assert!(!is_retryable(&mut condition, &1));
+        assert!(!is_retryable(&mut condition, &2));
+    }
+}
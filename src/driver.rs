@@ -0,0 +1,62 @@
+use std::io::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use futures::Future;
+use futures_timer::{Delay, TimerHandle};
+
+use super::strategy::deadline;
+
+/// Schedules the sleeps between attempts.
+///
+/// Implementing this trait decouples retry futures from the wall clock, which
+/// allows to drive them from a manual clock in simulations or tests.
+pub trait SleepDriver {
+    /// The future that resolves once a sleep has elapsed.
+    type Sleep: Future<Item=(), Error=Error>;
+
+    /// Returns a future that resolves once the given duration has elapsed.
+    fn sleep(&self, duration: Duration) -> Self::Sleep;
+
+    /// Resets a previously returned sleep, so that it resolves once the given duration has elapsed.
+    ///
+    /// By default a new sleep replaces the previous one.
+    fn reset(&self, sleep: &mut Self::Sleep, duration: Duration) {
+        *sleep = self.sleep(duration);
+    }
+}
+
+/// Sleep driver that uses [`futures-timer`](https://crates.io/crates/futures-timer).
+///
+/// This is the driver that retry futures use by default.
+#[derive(Clone, Default)]
+pub struct TimerDriver {
+    handle: TimerHandle
+}
+
+impl TimerDriver {
+    /// Creates a new driver, using the provided `handle` to schedule timeouts.
+    pub fn new(handle: TimerHandle) -> TimerDriver {
+        TimerDriver {
+            handle
+        }
+    }
+}
+
+impl fmt::Debug for TimerDriver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimerDriver").finish()
+    }
+}
+
+impl SleepDriver for TimerDriver {
+    type Sleep = Delay;
+
+    fn sleep(&self, duration: Duration) -> Delay {
+        Delay::new_handle(deadline(Instant::now(), duration), self.handle.clone())
+    }
+
+    fn reset(&self, sleep: &mut Delay, duration: Duration) {
+        sleep.reset_at(deadline(Instant::now(), duration));
+    }
+}
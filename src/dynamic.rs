@@ -0,0 +1,82 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use futures::Future;
+
+use super::strategy::Strategy;
+
+/// A boxed future that can be sent across threads.
+pub type BoxFuture<I, E> = Box<dyn Future<Item=I, Error=E> + Send>;
+
+/// A boxed action that produces boxed futures.
+pub type BoxAction<I, E> = Box<dyn FnMut() -> BoxFuture<I, E> + Send>;
+
+/// Retries boxed actions via a retry strategy.
+///
+/// Unlike [`Strategy::retry`](./struct.Strategy.html#method.retry), `run` is not generic
+/// over the action, so the same driver can be stored in a field or behind a trait object,
+/// and be called with different actions at runtime, as long as they agree on the item
+/// and error types.
+pub struct RetryDriver<I, E> {
+    strategy: Strategy,
+    marker: PhantomData<fn() -> (I, E)>
+}
+
+impl<I, E> RetryDriver<I, E> where I: Send + 'static, E: Send + 'static {
+    /// Creates a new driver that retries using the given strategy.
+    pub fn new(strategy: Strategy) -> RetryDriver<I, E> {
+        RetryDriver {
+            strategy,
+            marker: PhantomData
+        }
+    }
+
+    /// Run the given action, and retry on failure.
+    pub fn run(&self, action: BoxAction<I, E>) -> BoxFuture<I, E> {
+        Box::new(self.strategy.retry(action))
+    }
+}
+
+impl<I, E> fmt::Debug for RetryDriver<I, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryDriver")
+            .field("strategy", &self.strategy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{Future, future};
+
+    use super::*;
+
+    struct Client {
+        retry: RetryDriver<u32, String>
+    }
+
+    #[test]
+    fn runs_different_actions() {
+        let client = Client {
+            retry: RetryDriver::new(Strategy::fixed(Duration::from_millis(1)).with_max_retries(3))
+        };
+
+        let mut attempts = 0;
+        let first = client.retry.run(Box::new(move || -> BoxFuture<u32, String> {
+            attempts += 1;
+            if attempts < 3 {
+                Box::new(future::err(format!("attempt {} failed", attempts)))
+            } else {
+                Box::new(future::ok(attempts))
+            }
+        }));
+        assert_eq!(first.wait(), Ok(3));
+
+        let second = client.retry.run(Box::new(|| -> BoxFuture<u32, String> {
+            Box::new(future::err("always fails".to_owned()))
+        }));
+        assert_eq!(second.wait(), Err("always fails".to_owned()));
+    }
+}
@@ -0,0 +1,123 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Error returned by retry futures that can fail for reasons other than the action itself.
+///
+/// Besides `Operation`, each variant is only produced by the retry future noted on it.
+/// New variants may be added in the future, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RetryError<E> {
+    /// The action failed with the given error.
+    Operation(E),
+    /// The retry timed out before any attempt failed.
+    ///
+    /// Produced by [`RetryTimeout`](./struct.RetryTimeout.html).
+    Timeout,
+    /// The probe of the last attempt did not succeed, so the action was not run.
+    ///
+    /// Produced by [`RetryWithProbe`](./struct.RetryWithProbe.html).
+    ProbeFailed,
+    /// The stream of the last attempt ended without yielding an item.
+    ///
+    /// Produced by [`Strategy::retry_stream_first`](./struct.Strategy.html#method.retry_stream_first).
+    Empty,
+    /// The retry was cancelled, after the given attempt failed, if any.
+    ///
+    /// Produced by [`RetryUntil`](./struct.RetryUntil.html).
+    Cancelled(Option<E>),
+    /// All attempts failed.
+    ///
+    /// Produced by [`RetryRich`](./struct.RetryRich.html).
+    Exhausted {
+        /// The number of attempts that were made.
+        attempts: usize,
+        /// The time that elapsed between starting the first attempt and giving up.
+        total_elapsed: Duration,
+        /// The errors of all attempts, in order.
+        errors: Vec<E>
+    }
+}
+
+/// Error returned when the delays of a strategy cannot be collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// The strategy produces too many delays to collect them.
+    Unbounded
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScheduleError::Unbounded => f.write_str("strategy produces too many delays to collect them")
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RetryError::Operation(ref err) => err.fmt(f),
+            RetryError::Timeout => f.write_str("retry timed out before any attempt failed"),
+            RetryError::ProbeFailed => f.write_str("probe did not succeed before the last attempt"),
+            RetryError::Empty => f.write_str("stream ended without yielding an item"),
+            RetryError::Cancelled(Some(ref err)) => write!(f, "retry was cancelled after an error: {}", err),
+            RetryError::Cancelled(None) => f.write_str("retry was cancelled before any attempt failed"),
+            RetryError::Exhausted { attempts, total_elapsed, ref errors } => {
+                write!(f, "failed after {} attempts over {:?}; errors: [", attempts, total_elapsed)?;
+                for (i, err) in errors.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    err.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for RetryError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RetryError::Operation(ref err) => Some(err),
+            RetryError::Cancelled(Some(ref err)) => Some(err),
+            RetryError::Timeout | RetryError::ProbeFailed | RetryError::Empty | RetryError::Cancelled(None) => None,
+            RetryError::Exhausted { ref errors, .. } => errors.last().map(|err| err as &(dyn Error + 'static))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::fmt;
+    use std::time::Duration;
+    use super::RetryError;
+
+    #[derive(Debug)]
+    struct Failure(u32);
+
+    impl fmt::Display for Failure {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "failure {}", self.0)
+        }
+    }
+
+    impl Error for Failure {}
+
+    #[test]
+    fn exhausted_summarizes_all_errors() {
+        let err = RetryError::Exhausted {
+            attempts: 3,
+            total_elapsed: Duration::from_millis(20),
+            errors: vec![Failure(1), Failure(2), Failure(3)]
+        };
+
+        assert_eq!(err.to_string(), "failed after 3 attempts over 20ms; errors: [failure 1, failure 2, failure 3]");
+        assert_eq!(err.source().unwrap().to_string(), "failure 3");
+    }
+}
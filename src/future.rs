@@ -1,20 +1,27 @@
 use std::io::Error;
 use std::fmt;
-use std::time::Instant;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-use futures::{Async, Future, Poll};
-use futures_timer::{Delay, TimerHandle};
+use futures::{Async, Future, IntoFuture, Poll};
+use futures_timer::TimerHandle;
 
+use super::driver::{SleepDriver, TimerDriver};
 use super::strategy::{Strategy, StrategyIter};
-use super::action::Action;
+use super::action::{Action, ContextAction, RunContext};
 use super::condition::Condition;
+use super::retry_after::RetryAfter;
+use super::error::RetryError;
+use super::handle::RetryHandle;
 
-enum RetryState<A> where A: Action {
+enum RetryState<A, S> where A: Action {
     Running(A::Future),
-    Sleeping(Delay)
+    Sleeping(S)
 }
 
-impl<A: Action> RetryState<A> {
+impl<A: Action, S: Future<Item=(), Error=Error>> RetryState<A, S> {
     fn poll(&mut self) -> RetryFuturePoll<A> {
         match *self {
             RetryState::Running(ref mut future) =>
@@ -30,9 +37,12 @@ enum RetryFuturePoll<A> where A: Action {
     Sleeping(Poll<(), Error>)
 }
 
+/// Condition used by `Retry`, which retries on every error.
+type Always<E> = fn(&E) -> bool;
+
 /// Future that drives multiple attempts at an action via a retry strategy.
-pub struct Retry<A> where A: Action {
-    retry_if: RetryIf<A, fn(&A::Error) -> bool>
+pub struct Retry<A, D = TimerDriver> where A: Action, D: SleepDriver {
+    retry_if: RetryIf<A, Always<A::Error>, D>
 }
 
 impl<A: Action> Retry<A> {
@@ -44,18 +54,69 @@ impl<A: Action> Retry<A> {
     /// Creates a new retry future, using the provided `handle` to schedule timeouts.
     pub fn new_with_handle(handle: TimerHandle, strategy: &Strategy, action: A) -> Retry<A> {
         Retry {
-            retry_if: RetryIf::new_with_handle(handle, strategy, action, (|_| true) as fn(&A::Error) -> bool)
+            retry_if: RetryIf::new_with_handle(handle, strategy, action, (|_| true) as Always<A::Error>)
         }
     }
 }
 
-impl<A: Action> fmt::Debug for Retry<A> {
+impl<A: Action, D: SleepDriver> Retry<A, D> {
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(driver: D, strategy: &Strategy, action: A) -> Retry<A, D> {
+        Retry::with_strategy_iter(driver, strategy.iter(), action)
+    }
+
+    pub(crate) fn with_strategy_iter(driver: D, strategy_iter: StrategyIter, action: A) -> Retry<A, D> {
+        Retry {
+            retry_if: RetryIf::with_strategy_iter(driver, strategy_iter, action, (|_| true) as Always<A::Error>)
+        }
+    }
+
+    /// Limits the time that all attempts together may take.
+    ///
+    /// If the timeout elapses, the future resolves with the error of the most recent
+    /// attempt, or with `RetryError::Timeout` if no attempt has failed yet.
+    pub fn with_overall_timeout(self, timeout: Duration) -> RetryTimeout<A, Always<A::Error>, D> {
+        self.retry_if.with_overall_timeout(timeout)
+    }
+
+    /// Cancels the retry once the given flag is set.
+    ///
+    /// The flag is checked whenever the future is polled. Once it is set, the future resolves
+    /// with `RetryError::Cancelled`, carrying the error of the most recent attempt, if any.
+    /// Setting the flag does not wake up the future, so cancellation takes effect on the next poll.
+    pub fn with_cancel_flag(self, flag: Arc<AtomicBool>) -> RetryUntil<A, D> {
+        RetryUntil {
+            retry: self,
+            flag
+        }
+    }
+
+    pub(crate) fn observed(self) -> (Retry<A, D>, RetryHandle) {
+        let (retry_if, handle) = self.retry_if.observed();
+        (Retry { retry_if }, handle)
+    }
+
+    /// Reports timing information along with the item once an attempt succeeds.
+    pub fn timed(self) -> RetryTimed<A, D> {
+        RetryTimed {
+            retry: self
+        }
+    }
+
+    pub(crate) fn respecting_hint(self) -> Retry<A, D> where A::Error: RetryAfter {
+        Retry {
+            retry_if: self.retry_if.with_retry_after(<A::Error as RetryAfter>::retry_after)
+        }
+    }
+}
+
+impl<A: Action, D: SleepDriver> fmt::Debug for Retry<A, D> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Retry").finish()
     }
 }
 
-impl<A: Action> Future for Retry<A> {
+impl<A: Action, D: SleepDriver> Future for Retry<A, D> {
     type Item = A::Item;
     type Error = A::Error;
 
@@ -64,17 +125,381 @@ impl<A: Action> Future for Retry<A> {
     }
 }
 
+/// Timing information about a successful retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    elapsed: Duration,
+    attempt_latency: Duration
+}
+
+impl Timing {
+    /// The time that has elapsed since the first attempt was started,
+    /// including all attempts and the delays between them.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The time that the successful attempt took on its own.
+    pub fn attempt_latency(&self) -> Duration {
+        self.attempt_latency
+    }
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, and reports
+/// timing information along with the item.
+pub struct RetryTimed<A, D = TimerDriver> where A: Action, D: SleepDriver {
+    retry: Retry<A, D>
+}
+
+impl<A: Action, D: SleepDriver> fmt::Debug for RetryTimed<A, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryTimed").finish()
+    }
+}
+
+impl<A: Action, D: SleepDriver> Future for RetryTimed<A, D> {
+    type Item = (A::Item, Timing);
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let item = try_ready!(self.retry.poll());
+        let retry_if = &self.retry.retry_if;
+        let timing = Timing {
+            elapsed: retry_if.started.elapsed(),
+            attempt_latency: retry_if.attempt_started.elapsed()
+        };
+        Ok(Async::Ready((item, timing)))
+    }
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, passing a mutable
+/// context to every attempt.
+///
+/// Resolves with the item or error of the last attempt, along with the context.
+pub struct RetryWithContext<F, Ctx, T, D = TimerDriver>
+    where F: FnMut(&mut Ctx) -> T,
+          T: IntoFuture,
+          D: SleepDriver
+{
+    retry: Retry<ContextAction<F, Ctx>, D>
+}
+
+impl<F, Ctx, T> RetryWithContext<F, Ctx, T>
+    where F: FnMut(&mut Ctx) -> T,
+          T: IntoFuture
+{
+    /// Creates a new retry future.
+    pub fn new(strategy: &Strategy, action: F, context: Ctx) -> RetryWithContext<F, Ctx, T> {
+        RetryWithContext::new_with_handle(TimerHandle::default(), strategy, action, context)
+    }
+
+    /// Creates a new retry future, using the provided `handle` to schedule timeouts.
+    pub fn new_with_handle(
+        handle: TimerHandle,
+        strategy: &Strategy,
+        action: F,
+        context: Ctx
+    ) -> RetryWithContext<F, Ctx, T> {
+        RetryWithContext::new_with_driver(TimerDriver::new(handle), strategy, action, context)
+    }
+}
+
+impl<F, Ctx, T, D> RetryWithContext<F, Ctx, T, D>
+    where F: FnMut(&mut Ctx) -> T,
+          T: IntoFuture,
+          D: SleepDriver
+{
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(
+        driver: D,
+        strategy: &Strategy,
+        action: F,
+        context: Ctx
+    ) -> RetryWithContext<F, Ctx, T, D> {
+        RetryWithContext {
+            retry: Retry::new_with_driver(driver, strategy, ContextAction::new(action, context))
+        }
+    }
+}
+
+impl<F, Ctx, T, D> fmt::Debug for RetryWithContext<F, Ctx, T, D>
+    where F: FnMut(&mut Ctx) -> T,
+          T: IntoFuture,
+          D: SleepDriver
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryWithContext").finish()
+    }
+}
+
+impl<F, Ctx, T, D> Future for RetryWithContext<F, Ctx, T, D>
+    where F: FnMut(&mut Ctx) -> T,
+          T: IntoFuture,
+          D: SleepDriver
+{
+    type Item = (T::Item, Ctx);
+    type Error = (T::Error, Ctx);
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.retry.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(item)) => Ok(Async::Ready((item, self.retry.retry_if.action.take_context()))),
+            Err(err) => Err((err, self.retry.retry_if.action.take_context()))
+        }
+    }
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, until it is
+/// cancelled by a flag.
+///
+/// Created by [`Retry::with_cancel_flag`](./struct.Retry.html#method.with_cancel_flag).
+pub struct RetryUntil<A, D = TimerDriver> where A: Action, D: SleepDriver {
+    retry: Retry<A, D>,
+    flag: Arc<AtomicBool>
+}
+
+impl<A: Action, D: SleepDriver> fmt::Debug for RetryUntil<A, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryUntil").finish()
+    }
+}
+
+impl<A: Action, D: SleepDriver> Future for RetryUntil<A, D> {
+    type Item = A::Item;
+    type Error = RetryError<A::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if self.flag.load(Ordering::SeqCst) {
+            return Err(RetryError::Cancelled(self.retry.retry_if.last_error.take()))
+        }
+        self.retry.poll().map_err(RetryError::Operation)
+    }
+}
+
+/// Action that stores the errors of all runs, and fails without an error of its own.
+struct Collecting<A> where A: Action {
+    action: A,
+    errors: Arc<Mutex<Vec<A::Error>>>
+}
+
+impl<A: Action> Action for Collecting<A> {
+    type Item = A::Item;
+    type Error = ();
+    type Future = CollectingFuture<A>;
+
+    fn run(&mut self) -> Self::Future {
+        CollectingFuture {
+            future: self.action.run(),
+            errors: self.errors.clone()
+        }
+    }
+
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        CollectingFuture {
+            future: self.action.run_with_context(context),
+            errors: self.errors.clone()
+        }
+    }
+}
+
+struct CollectingFuture<A> where A: Action {
+    future: A::Future,
+    errors: Arc<Mutex<Vec<A::Error>>>
+}
+
+impl<A: Action> Future for CollectingFuture<A> {
+    type Item = A::Item;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.future.poll().map_err(|err| {
+            self.errors.lock().unwrap().push(err);
+        })
+    }
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, and fails
+/// with the errors of all attempts.
+///
+/// Once all attempts have failed, the future resolves with
+/// [`RetryError::Exhausted`](./enum.RetryError.html#variant.Exhausted).
+pub struct RetryRich<A, D = TimerDriver> where A: Action, D: SleepDriver {
+    retry: Retry<Collecting<A>, D>,
+    errors: Arc<Mutex<Vec<A::Error>>>,
+    started: Instant
+}
+
+impl<A: Action> RetryRich<A> {
+    /// Creates a new retry future.
+    pub fn new(strategy: &Strategy, action: A) -> RetryRich<A> {
+        RetryRich::new_with_handle(TimerHandle::default(), strategy, action)
+    }
+
+    /// Creates a new retry future, using the provided `handle` to schedule timeouts.
+    pub fn new_with_handle(handle: TimerHandle, strategy: &Strategy, action: A) -> RetryRich<A> {
+        RetryRich::new_with_driver(TimerDriver::new(handle), strategy, action)
+    }
+}
+
+impl<A: Action, D: SleepDriver> RetryRich<A, D> {
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(driver: D, strategy: &Strategy, action: A) -> RetryRich<A, D> {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let started = Instant::now();
+        let action = Collecting {
+            action,
+            errors: errors.clone()
+        };
+        RetryRich {
+            retry: Retry::new_with_driver(driver, strategy, action),
+            errors,
+            started
+        }
+    }
+}
+
+impl<A: Action, D: SleepDriver> fmt::Debug for RetryRich<A, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryRich").finish()
+    }
+}
+
+impl<A: Action, D: SleepDriver> Future for RetryRich<A, D> {
+    type Item = A::Item;
+    type Error = RetryError<A::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.retry.poll() {
+            Ok(async) => Ok(async),
+            Err(()) => {
+                let errors = mem::take(&mut *self.errors.lock().unwrap());
+                Err(RetryError::Exhausted {
+                    attempts: errors.len(),
+                    total_elapsed: self.started.elapsed(),
+                    errors
+                })
+            }
+        }
+    }
+}
+
+enum OrElseState<G, F> {
+    Retrying(Option<G>),
+    Finalizing(F)
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, and runs a fallback
+/// once the retries are exhausted.
+pub struct RetryOrElse<A, G, F, D = TimerDriver>
+    where A: Action,
+          G: FnOnce(A::Error) -> F,
+          F: IntoFuture<Item=A::Item, Error=A::Error>,
+          D: SleepDriver
+{
+    retry: Retry<A, D>,
+    state: OrElseState<G, F::Future>
+}
+
+impl<A, G, F> RetryOrElse<A, G, F>
+    where A: Action,
+          G: FnOnce(A::Error) -> F,
+          F: IntoFuture<Item=A::Item, Error=A::Error>
+{
+    /// Creates a new retry future, running `on_exhausted` with the last error once the retries
+    /// are exhausted.
+    pub fn new(strategy: &Strategy, action: A, on_exhausted: G) -> RetryOrElse<A, G, F> {
+        RetryOrElse::new_with_handle(TimerHandle::default(), strategy, action, on_exhausted)
+    }
+
+    /// Creates a new retry future, using the provided `handle` to schedule timeouts.
+    pub fn new_with_handle(
+        handle: TimerHandle,
+        strategy: &Strategy,
+        action: A,
+        on_exhausted: G
+    ) -> RetryOrElse<A, G, F> {
+        RetryOrElse::new_with_driver(TimerDriver::new(handle), strategy, action, on_exhausted)
+    }
+}
+
+impl<A, G, F, D> RetryOrElse<A, G, F, D>
+    where A: Action,
+          G: FnOnce(A::Error) -> F,
+          F: IntoFuture<Item=A::Item, Error=A::Error>,
+          D: SleepDriver
+{
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(
+        driver: D,
+        strategy: &Strategy,
+        action: A,
+        on_exhausted: G
+    ) -> RetryOrElse<A, G, F, D> {
+        RetryOrElse {
+            retry: Retry::new_with_driver(driver, strategy, action),
+            state: OrElseState::Retrying(Some(on_exhausted))
+        }
+    }
+}
+
+impl<A, G, F, D> fmt::Debug for RetryOrElse<A, G, F, D>
+    where A: Action,
+          G: FnOnce(A::Error) -> F,
+          F: IntoFuture<Item=A::Item, Error=A::Error>,
+          D: SleepDriver
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryOrElse").finish()
+    }
+}
+
+impl<A, G, F, D> Future for RetryOrElse<A, G, F, D>
+    where A: Action,
+          G: FnOnce(A::Error) -> F,
+          F: IntoFuture<Item=A::Item, Error=A::Error>,
+          D: SleepDriver
+{
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let fallback = match self.state {
+                OrElseState::Retrying(ref mut on_exhausted) => match self.retry.poll() {
+                    Err(err) => {
+                        let on_exhausted = on_exhausted.take().expect("cannot poll RetryOrElse twice");
+                        on_exhausted(err).into_future()
+                    },
+                    result => return result
+                },
+                OrElseState::Finalizing(ref mut future) => return future.poll()
+            };
+            self.state = OrElseState::Finalizing(fallback);
+        }
+    }
+}
+
 /// Future that drives multiple attempts at an action via a retry strategy. Retries are only attempted if
 /// the `Error` returned by the future satisfies a given condition.
-pub struct RetryIf<A, C>
+pub struct RetryIf<A, C, D = TimerDriver>
     where A: Action,
-          C: Condition<A::Error>
+          C: Condition<A::Error>,
+          D: SleepDriver
 {
     strategy_iter: StrategyIter,
-    state: RetryState<A>,
+    state: RetryState<A, D::Sleep>,
     action: A,
-    handle: TimerHandle,
-    condition: C
+    driver: D,
+    condition: C,
+    started: Instant,
+    attempt_started: Instant,
+    attempts: usize,
+    retry_after: fn(&A::Error) -> Option<Duration>,
+    select: fn(&mut C, &mut StrategyIter),
+    exhausted: fn(&C, &StrategyIter) -> bool,
+    idle_delay: Option<D::Sleep>,
+    last_error: Option<A::Error>,
+    progress: Option<RetryHandle>
 }
 
 impl<A, C> RetryIf<A, C>
@@ -94,65 +519,210 @@ impl<A, C> RetryIf<A, C>
     pub fn new_with_handle(
         handle: TimerHandle,
         strategy: &Strategy,
-        mut action: A,
+        action: A,
         condition: C
     ) -> RetryIf<A, C> {
+        RetryIf::new_with_driver(TimerDriver::new(handle), strategy, action, condition)
+    }
+}
+
+impl<A, C, D> RetryIf<A, C, D>
+    where A: Action,
+          C: Condition<A::Error>,
+          D: SleepDriver
+{
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(
+        driver: D,
+        strategy: &Strategy,
+        action: A,
+        condition: C
+    ) -> RetryIf<A, C, D> {
+        RetryIf::with_strategy_iter(driver, strategy.iter(), action, condition)
+    }
+
+    pub(crate) fn with_strategy_iter(
+        driver: D,
+        strategy_iter: StrategyIter,
+        action: A,
+        condition: C
+    ) -> RetryIf<A, C, D> {
+        RetryIf::with_selection(driver, strategy_iter, action, condition, |_, _| {}, |_, strategy_iter| strategy_iter.is_exhausted())
+    }
+
+    /// Creates a new retry future, in which the condition picks the strategy iterator that
+    /// schedules the retry after each accepted error, and decides whether another retry
+    /// is possible at all.
+    pub(crate) fn with_selection(
+        driver: D,
+        strategy_iter: StrategyIter,
+        mut action: A,
+        condition: C,
+        select: fn(&mut C, &mut StrategyIter),
+        exhausted: fn(&C, &StrategyIter) -> bool
+    ) -> RetryIf<A, C, D> {
+        let started = Instant::now();
+        let is_last = exhausted(&condition, &strategy_iter);
+        let future = action.run_with_context(RunContext::new(0, is_last, Duration::from_secs(0)));
         RetryIf {
-            strategy_iter: strategy.iter(),
-            state: RetryState::Running(action.run()),
-            action: action,
-            handle: handle,
-            condition: condition,
+            strategy_iter,
+            state: RetryState::Running(future),
+            action,
+            driver,
+            condition,
+            started,
+            attempt_started: Instant::now(),
+            attempts: 1,
+            retry_after: |_| None,
+            select,
+            exhausted,
+            idle_delay: None,
+            last_error: None,
+            progress: None
+        }
+    }
+
+    pub(crate) fn observed(mut self) -> (RetryIf<A, C, D>, RetryHandle) {
+        let handle = RetryHandle::default();
+        self.progress = Some(handle.clone());
+        self.update_progress();
+        (self, handle)
+    }
+
+    fn update_progress(&self) {
+        if let Some(ref progress) = self.progress {
+            progress.update(self.attempts, self.strategy_iter.retries_remaining());
         }
     }
 
-    fn attempt(&mut self) -> Poll<A::Item, A::Error> {
-        let future = self.action.run();
-        self.state = RetryState::Running(future);
-        self.poll()
+    /// Limits the time that all attempts together may take.
+    ///
+    /// If the timeout elapses, the future resolves with the error of the most recent
+    /// attempt, or with `RetryError::Timeout` if no attempt has failed yet.
+    pub fn with_overall_timeout(self, timeout: Duration) -> RetryTimeout<A, C, D> {
+        let delay = self.driver.sleep(timeout.checked_sub(self.started.elapsed()).unwrap_or_default());
+        RetryTimeout {
+            retry_if: self,
+            delay
+        }
+    }
+
+    pub(crate) fn with_retry_after(mut self, retry_after: fn(&A::Error) -> Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    fn attempt(&mut self) {
+        let is_last = (self.exhausted)(&self.condition, &self.strategy_iter);
+        let context = RunContext::new(self.attempts, is_last, self.started.elapsed());
+        self.attempt_started = Instant::now();
+        let future = self.action.run_with_context(context);
+        self.attempts += 1;
+        if let RetryState::Sleeping(delay) = mem::replace(&mut self.state, RetryState::Running(future)) {
+            self.idle_delay = Some(delay);
+        }
+        self.update_progress();
     }
 
-    fn retry(&mut self, err: A::Error) -> Poll<A::Item, A::Error> {
+    fn retry(&mut self, err: A::Error) -> Result<(), A::Error> {
         match self.strategy_iter.next() {
             None => Err(err),
-            Some(duration) => {
-                let instant = Instant::now() + duration;
-                let future = Delay::new_handle(instant, self.handle.clone());
+            Some(computed) => {
+                let duration = match (self.retry_after)(&err) {
+                    Some(hint) => self.strategy_iter.clamp(hint),
+                    None => computed
+                };
+                let future = match self.idle_delay.take() {
+                    Some(mut delay) => {
+                        self.driver.reset(&mut delay, duration);
+                        delay
+                    },
+                    None => self.driver.sleep(duration)
+                };
                 self.state = RetryState::Sleeping(future);
-                self.poll()
+                self.last_error = Some(err);
+                self.update_progress();
+                Ok(())
             }
         }
     }
 }
 
-impl<A: Action, C: Condition<A::Error>> fmt::Debug for RetryIf<A, C> {
+impl<A: Action, C: Condition<A::Error>, D: SleepDriver> fmt::Debug for RetryIf<A, C, D> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("RetryIf").finish()
     }
 }
 
-impl<A, C> Future for RetryIf<A, C>
+impl<A, C, D> Future for RetryIf<A, C, D>
     where A: Action,
-          C: Condition<A::Error>
+          C: Condition<A::Error>,
+          D: SleepDriver
 {
     type Item = A::Item;
     type Error = A::Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        match self.state.poll() {
-            RetryFuturePoll::Running(poll_result) => match poll_result {
-                Ok(async) => Ok(async),
-                Err(err) => {
-                    if self.condition.should_retry(&err) {
-                        self.retry(err)
-                    } else {
-                        Err(err)
+        loop {
+            match self.state.poll() {
+                RetryFuturePoll::Running(poll_result) => match poll_result {
+                    Ok(async) => return Ok(async),
+                    Err(err) => {
+                        let decision = self.condition.decide(&err, self.started.elapsed());
+                        if decision.retry {
+                            (self.select)(&mut self.condition, &mut self.strategy_iter);
+                            self.strategy_iter.extend(decision.extra_retries);
+                            self.retry(err)?
+                        } else {
+                            return Err(err)
+                        }
                     }
+                },
+                RetryFuturePoll::Sleeping(poll_result) => match poll_result.unwrap() {
+                    Async::NotReady => return Ok(Async::NotReady),
+                    Async::Ready(_) => self.attempt()
                 }
-            },
-            RetryFuturePoll::Sleeping(poll_result) => match poll_result.unwrap() {
-                Async::NotReady => Ok(Async::NotReady),
-                Async::Ready(_) => self.attempt()
+            }
+        }
+    }
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, limiting the time
+/// that all attempts together may take.
+pub struct RetryTimeout<A, C, D = TimerDriver>
+    where A: Action,
+          C: Condition<A::Error>,
+          D: SleepDriver
+{
+    retry_if: RetryIf<A, C, D>,
+    delay: D::Sleep
+}
+
+impl<A: Action, C: Condition<A::Error>, D: SleepDriver> fmt::Debug for RetryTimeout<A, C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryTimeout").finish()
+    }
+}
+
+impl<A, C, D> Future for RetryTimeout<A, C, D>
+    where A: Action,
+          C: Condition<A::Error>,
+          D: SleepDriver
+{
+    type Item = A::Item;
+    type Error = RetryError<A::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.retry_if.poll() {
+            Ok(Async::NotReady) => {},
+            Ok(Async::Ready(item)) => return Ok(Async::Ready(item)),
+            Err(err) => return Err(RetryError::Operation(err))
+        }
+        match self.delay.poll().unwrap() {
+            Async::NotReady => Ok(Async::NotReady),
+            Async::Ready(_) => match self.retry_if.last_error.take() {
+                Some(err) => Err(RetryError::Operation(err)),
+                None => Err(RetryError::Timeout)
             }
         }
     }
@@ -160,9 +730,20 @@ impl<A, C> Future for RetryIf<A, C>
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
-    use futures::Future;
-    use super::Strategy;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use futures::{executor, future, Async, Future, Poll};
+    use futures::executor::Notify;
+    use super::{Error, RetryIf, RetryState, Strategy};
+    use super::super::driver::SleepDriver;
+    use super::super::retry_after::RetryAfter;
+    use super::super::error::RetryError;
+    use super::super::condition::PerCategory;
+    use super::super::action::{RunContext, WithRunContext};
 
     #[test]
     fn attempts_just_once() {
@@ -235,4 +816,515 @@ mod tests {
         assert_eq!(res, Err(3));
         assert_eq!(num_calls, 3);
     }
+
+    #[test]
+    fn attempts_retry_only_while_elapsed_time_is_within_limit() {
+        let s = Strategy::fixed(Duration::from_millis(100))
+            .with_max_retries(10);
+        let mut num_calls = 0;
+        let res = {
+            let action = || {
+                num_calls += 1;
+                Err::<(), u64>(42)
+            };
+            let fut = s.retry_if_elapsed(action, |_: &u64, elapsed: Duration| {
+                elapsed < Duration::from_millis(250)
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(42));
+        assert_eq!(num_calls, 4);
+    }
+
+    #[test]
+    fn attempts_many_zero_delay_retries_without_recursing() {
+        let s = Strategy::fixed(Duration::from_millis(0))
+            .with_max_retries(10_000);
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry(|| {
+                num_calls += 1;
+                Err::<(), u64>(42)
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(42));
+        assert_eq!(num_calls, 10_001);
+    }
+
+    #[test]
+    fn tells_the_action_about_its_last_attempt() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let mut contexts = Vec::new();
+        let res = {
+            let fut = s.retry(WithRunContext(|context: RunContext| {
+                contexts.push((context.attempt(), context.is_last()));
+                Err::<(), u64>(42)
+            }));
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(42));
+        assert_eq!(contexts, vec![(0, false), (1, false), (2, true)]);
+    }
+
+    #[test]
+    fn does_not_compute_a_delay_before_the_first_failure() {
+        use std::sync::atomic::AtomicUsize;
+        use super::super::strategy::RandomSource;
+
+        struct Counting(Arc<AtomicUsize>);
+
+        impl RandomSource for Counting {
+            fn jitter(&mut self, max: Duration) -> Duration {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                max
+            }
+        }
+
+        let draws = Arc::new(AtomicUsize::new(0));
+        let hook_calls = Arc::new(AtomicUsize::new(0));
+        let calls = hook_calls.clone();
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_jitter(true)
+            .with_random_source(Counting(draws.clone()))
+            .with_delay_hook(move |_, delay| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                delay
+            });
+        let res = s.retry(WithRunContext(|context: RunContext| {
+            assert!(!context.is_last());
+            Ok::<u64, u64>(42)
+        })).wait();
+
+        assert_eq!(res, Ok(42));
+        assert_eq!(draws.load(Ordering::SeqCst), 0);
+        assert_eq!(hook_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn sleeps_for_the_delay_suggested_by_the_error() {
+        #[derive(Debug, PartialEq)]
+        struct RateLimited;
+
+        impl RetryAfter for RateLimited {
+            fn retry_after(&self) -> Option<Duration> {
+                Some(Duration::from_millis(200))
+            }
+        }
+
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let mut num_calls = 0;
+        let started = Instant::now();
+        let res = {
+            let fut = s.retry_respecting_hint(|| {
+                num_calls += 1;
+                Err::<(), RateLimited>(RateLimited)
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(RateLimited));
+        assert_eq!(num_calls, 2);
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn attempts_retry_within_independent_budgets_per_category() {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum Kind { Timeout, Reset }
+
+        let kinds = [Kind::Reset, Kind::Reset, Kind::Reset, Kind::Timeout, Kind::Timeout, Kind::Reset];
+        let s = Strategy::fixed(Duration::from_millis(1))
+            .with_max_retries(100);
+        let mut num_calls = 0;
+        let res = {
+            let action = || {
+                num_calls += 1;
+                Err::<(), Kind>(kinds[num_calls - 1])
+            };
+            let condition = PerCategory::new(|kind: &Kind| *kind)
+                .with_budget(Kind::Timeout, 2)
+                .with_budget(Kind::Reset, 3);
+            let fut = s.retry_if(action, condition);
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(Kind::Reset));
+        assert_eq!(num_calls, 6);
+    }
+
+    #[test]
+    fn reuses_the_delay_across_attempts() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(5);
+        let mut fut = RetryIf::new(&s, || Err::<(), u64>(42), |_: &u64| true);
+        assert!(fut.idle_delay.is_none());
+
+        fut.retry(42).unwrap();
+        assert!(fut.idle_delay.is_none());
+        fut.attempt();
+        assert!(fut.idle_delay.is_some());
+
+        fut.retry(42).unwrap();
+        assert!(fut.idle_delay.is_none());
+        match fut.state {
+            RetryState::Sleeping(_) => {},
+            RetryState::Running(_) => panic!("expected to be sleeping")
+        }
+
+        assert_eq!(fut.wait(), Err(42));
+    }
+
+    #[test]
+    fn runs_the_fallback_once_retries_are_exhausted() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry_or_else(|| {
+                num_calls += 1;
+                Err::<u64, u64>(42)
+            }, |err| Ok::<u64, u64>(err + 1));
+            fut.wait()
+        };
+
+        assert_eq!(res, Ok(43));
+        assert_eq!(num_calls, 3);
+    }
+
+    #[test]
+    fn attempts_synchronous_function_until_success() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry_sync_in_place(|| {
+                num_calls += 1;
+                if num_calls < 3 {
+                    Err::<u64, u64>(42)
+                } else {
+                    Ok::<u64, u64>(7)
+                }
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Ok(7));
+        assert_eq!(num_calls, 3);
+    }
+
+    struct DropTracked {
+        result: Option<Result<(), u64>>,
+        drops: Rc<Cell<usize>>
+    }
+
+    impl Future for DropTracked {
+        type Item = ();
+        type Error = u64;
+
+        fn poll(&mut self) -> Poll<(), u64> {
+            match self.result.take() {
+                Some(Ok(())) => Ok(Async::Ready(())),
+                Some(Err(err)) => Err(err),
+                None => Ok(Async::NotReady)
+            }
+        }
+    }
+
+    impl Drop for DropTracked {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+        }
+    }
+
+    struct NoopNotify;
+
+    impl Notify for NoopNotify {
+        fn notify(&self, _id: usize) {}
+    }
+
+    #[test]
+    fn drops_cleanly_while_sleeping() {
+        let s = Strategy::fixed(Duration::from_secs(10));
+        let drops = Rc::new(Cell::new(0));
+        let mut fut = {
+            let drops = drops.clone();
+            executor::spawn(s.retry(move || DropTracked { result: Some(Err(42)), drops: drops.clone() }))
+        };
+
+        let notify = Arc::new(NoopNotify);
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        assert_eq!(drops.get(), 1);
+
+        drop(fut);
+        assert_eq!(drops.get(), 1);
+        assert_eq!(Rc::strong_count(&drops), 1);
+    }
+
+    #[test]
+    fn drops_running_action_future() {
+        let s = Strategy::fixed(Duration::from_secs(10));
+        let drops = Rc::new(Cell::new(0));
+        let mut fut = {
+            let drops = drops.clone();
+            executor::spawn(s.retry(move || DropTracked { result: None, drops: drops.clone() }))
+        };
+
+        let notify = Arc::new(NoopNotify);
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        assert_eq!(drops.get(), 0);
+
+        drop(fut);
+        assert_eq!(drops.get(), 1);
+        assert_eq!(Rc::strong_count(&drops), 1);
+    }
+
+    #[test]
+    fn overall_timeout_resolves_with_the_last_error() {
+        let s = Strategy::fixed(Duration::from_millis(100))
+            .with_max_retries(10);
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry(|| {
+                num_calls += 1;
+                Err::<(), u64>(num_calls)
+            }).with_overall_timeout(Duration::from_millis(250));
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(RetryError::Operation(3)));
+        assert_eq!(num_calls, 3);
+    }
+
+    #[test]
+    fn overall_timeout_without_failed_attempt() {
+        let s = Strategy::fixed(Duration::from_millis(100));
+        let res = s.retry(future::empty::<(), u64>)
+            .with_overall_timeout(Duration::from_millis(10))
+            .wait();
+
+        assert_eq!(res, Err(RetryError::Timeout));
+    }
+
+    #[test]
+    fn cancel_flag_cancels_on_the_next_poll() {
+        let s = Strategy::fixed(Duration::from_secs(10));
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut fut = executor::spawn(s.retry_until(|| Err::<(), u64>(42), flag.clone()));
+        let notify = Arc::new(NoopNotify);
+
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        flag.store(true, Ordering::SeqCst);
+        assert_eq!(fut.poll_future_notify(&notify, 0), Err(RetryError::Cancelled(Some(42))));
+    }
+
+    #[test]
+    fn cancel_flag_before_any_failure() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let flag = Arc::new(AtomicBool::new(true));
+        let res = s.retry_until(future::empty::<(), u64>, flag).wait();
+
+        assert_eq!(res, Err(RetryError::Cancelled(None)));
+    }
+
+    #[test]
+    fn handle_reports_progress_between_polls() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(3);
+        let (fut, handle) = s.retry_with_handle(|| Err::<(), u64>(42));
+        let mut fut = executor::spawn(fut);
+        let notify = Arc::new(NoopNotify);
+
+        assert_eq!(handle.attempts_made(), 1);
+        assert_eq!(handle.retries_remaining(), 3);
+
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        assert_eq!(handle.attempts_made(), 1);
+        assert_eq!(handle.retries_remaining(), 2);
+
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        assert_eq!(handle.attempts_made(), 2);
+        assert_eq!(handle.retries_remaining(), 1);
+    }
+
+    #[test]
+    fn attempts_once_per_replayed_delay() {
+        let s = Strategy::from_delays(&[
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+            Duration::from_millis(500)
+        ]);
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry(|| {
+                num_calls += 1;
+                Err::<(), u64>(42)
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(42));
+        assert_eq!(num_calls, 4);
+    }
+
+    #[derive(Clone, Default)]
+    struct ManualDriver {
+        sleeps: Rc<RefCell<Vec<(Duration, bool)>>>
+    }
+
+    impl ManualDriver {
+        fn requested(&self) -> Vec<Duration> {
+            self.sleeps.borrow().iter().map(|&(duration, _)| duration).collect()
+        }
+
+        fn advance(&self) {
+            for sleep in self.sleeps.borrow_mut().iter_mut() {
+                sleep.1 = true;
+            }
+        }
+    }
+
+    struct ManualSleep {
+        index: usize,
+        driver: ManualDriver
+    }
+
+    impl Future for ManualSleep {
+        type Item = ();
+        type Error = Error;
+
+        fn poll(&mut self) -> Poll<(), Error> {
+            if self.driver.sleeps.borrow()[self.index].1 {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            }
+        }
+    }
+
+    impl SleepDriver for ManualDriver {
+        type Sleep = ManualSleep;
+
+        fn sleep(&self, duration: Duration) -> ManualSleep {
+            let mut sleeps = self.sleeps.borrow_mut();
+            sleeps.push((duration, false));
+            ManualSleep { index: sleeps.len() - 1, driver: self.clone() }
+        }
+    }
+
+    #[test]
+    fn sleeps_via_the_given_driver() {
+        let s = Strategy::exponential(Duration::from_secs(10));
+        let driver = ManualDriver::default();
+        let mut num_calls = 0;
+        let mut fut = executor::spawn(s.retry_with(|| {
+            num_calls += 1;
+            if num_calls < 3 {
+                Err::<usize, u64>(42)
+            } else {
+                Ok::<usize, u64>(num_calls)
+            }
+        }, driver.clone()));
+        let notify = Arc::new(NoopNotify);
+
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        assert_eq!(driver.requested(), vec![Duration::from_secs(10)]);
+
+        driver.advance();
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        assert_eq!(driver.requested(), vec![Duration::from_secs(10), Duration::from_secs(20)]);
+
+        driver.advance();
+        assert_eq!(fut.poll_future_notify(&notify, 0), Ok(Async::Ready(3)));
+    }
+
+    #[test]
+    fn reports_latency_of_the_successful_attempt_separately() {
+        let s = Strategy::fixed(Duration::from_millis(50));
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry_timed(|| {
+                num_calls += 1;
+                let fail = num_calls < 3;
+                futures_timer::Delay::new(Duration::from_millis(if fail { 0 } else { 100 }))
+                    .then(move |_| if fail { Err(42) } else { Ok::<u64, u64>(7) })
+            });
+            fut.wait()
+        };
+
+        let (item, timing) = res.unwrap();
+        assert_eq!(item, 7);
+        assert!(timing.attempt_latency() >= Duration::from_millis(100));
+        assert!(timing.elapsed() >= timing.attempt_latency() + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn threads_the_context_through_every_attempt() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let res = s.retry_with_context(|attempts: &mut Vec<usize>| {
+            let attempt = attempts.len();
+            attempts.push(attempt);
+            if attempt < 2 {
+                Err::<&str, u64>(42)
+            } else {
+                Ok::<&str, u64>("done")
+            }
+        }, Vec::new()).wait();
+
+        assert_eq!(res, Ok(("done", vec![0, 1, 2])));
+    }
+
+    #[test]
+    fn returns_the_context_along_with_the_last_error() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let res = s.retry_with_context(|attempts: &mut usize| {
+            *attempts += 1;
+            Err::<(), u64>(42)
+        }, 0).wait();
+
+        assert_eq!(res, Err((42, 2)));
+    }
+
+    #[test]
+    fn retry_rich_collects_the_errors_of_all_attempts() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let mut num_calls = 0;
+        let res = s.retry_rich(|| {
+            num_calls += 1;
+            Err::<(), u64>(num_calls)
+        }).wait();
+
+        match res {
+            Err(RetryError::Exhausted { attempts, total_elapsed, errors }) => {
+                assert_eq!(attempts, 3);
+                assert_eq!(errors, vec![1, 2, 3]);
+                assert!(total_elapsed >= Duration::from_millis(20));
+            },
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn retry_rich_resolves_with_the_first_success() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let mut num_calls = 0;
+        let res = s.retry_rich(|| {
+            num_calls += 1;
+            if num_calls < 2 {
+                Err::<u64, u64>(num_calls)
+            } else {
+                Ok::<u64, u64>(num_calls)
+            }
+        }).wait();
+
+        assert_eq!(res, Ok(2));
+    }
 }
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Default)]
+struct Progress {
+    attempts_made: AtomicUsize,
+    retries_remaining: AtomicUsize
+}
+
+/// Handle to observe the progress of a retry future.
+///
+/// The handle can be cloned and sent to other threads, and is updated as the
+/// retry future makes progress.
+///
+/// Created by [`Strategy::retry_with_handle`](./struct.Strategy.html#method.retry_with_handle).
+#[derive(Debug, Clone, Default)]
+pub struct RetryHandle {
+    progress: Arc<Progress>
+}
+
+impl RetryHandle {
+    /// Returns the number of attempts that have been made so far, including the one in progress.
+    pub fn attempts_made(&self) -> usize {
+        self.progress.attempts_made.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of retries that remain before the strategy is exhausted.
+    pub fn retries_remaining(&self) -> usize {
+        self.progress.retries_remaining.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn update(&self, attempts_made: usize, retries_remaining: usize) {
+        self.progress.attempts_made.store(attempts_made, Ordering::SeqCst);
+        self.progress.retries_remaining.store(retries_remaining, Ordering::SeqCst);
+    }
+}
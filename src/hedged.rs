@@ -0,0 +1,167 @@
+use std::fmt;
+use std::time::Instant;
+
+use futures::{Async, Future, Poll};
+use futures_timer::TimerHandle;
+
+use super::strategy::{Strategy, StrategyIter};
+use super::action::{Action, RunContext};
+use super::driver::{SleepDriver, TimerDriver};
+
+/// Future that hedges an action via a retry strategy.
+///
+/// Instead of waiting for an attempt to fail, a new attempt is launched each time
+/// the delay elapses, while the previous attempts are kept running. The first attempt
+/// that succeeds wins. If all attempts fail, the future resolves with the last error.
+pub struct RetryHedged<A, D = TimerDriver> where A: Action, D: SleepDriver {
+    strategy_iter: StrategyIter,
+    running: Vec<A::Future>,
+    delay: Option<D::Sleep>,
+    action: A,
+    driver: D,
+    last_error: Option<A::Error>,
+    started: Instant,
+    attempts: usize
+}
+
+impl<A: Action> RetryHedged<A> {
+    /// Creates a new hedged retry future.
+    pub fn new(strategy: &Strategy, action: A) -> RetryHedged<A> {
+        RetryHedged::new_with_handle(TimerHandle::default(), strategy, action)
+    }
+
+    /// Creates a new hedged retry future, using the provided `handle` to schedule timeouts.
+    pub fn new_with_handle(handle: TimerHandle, strategy: &Strategy, action: A) -> RetryHedged<A> {
+        RetryHedged::new_with_driver(TimerDriver::new(handle), strategy, action)
+    }
+}
+
+impl<A: Action, D: SleepDriver> RetryHedged<A, D> {
+    /// Creates a new hedged retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(driver: D, strategy: &Strategy, action: A) -> RetryHedged<A, D> {
+        let mut hedged = RetryHedged {
+            strategy_iter: strategy.iter(),
+            running: Vec::new(),
+            delay: None,
+            action,
+            driver,
+            last_error: None,
+            started: Instant::now(),
+            attempts: 0
+        };
+        hedged.attempt();
+        hedged
+    }
+
+    fn attempt(&mut self) {
+        let is_last = self.strategy_iter.is_exhausted();
+        let context = RunContext::new(self.attempts, is_last, self.started.elapsed());
+        self.attempts += 1;
+        let future = self.action.run_with_context(context);
+        self.running.push(future);
+        let driver = &self.driver;
+        self.delay = self.strategy_iter.next().map(|duration| driver.sleep(duration));
+    }
+}
+
+impl<A: Action, D: SleepDriver> fmt::Debug for RetryHedged<A, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryHedged").finish()
+    }
+}
+
+impl<A: Action, D: SleepDriver> Future for RetryHedged<A, D> {
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let mut i = 0;
+            while i < self.running.len() {
+                match self.running[i].poll() {
+                    Ok(Async::Ready(item)) => return Ok(Async::Ready(item)),
+                    Ok(Async::NotReady) => i += 1,
+                    Err(err) => {
+                        drop(self.running.swap_remove(i));
+                        self.last_error = Some(err);
+                    }
+                }
+            }
+
+            let elapsed = match self.delay {
+                Some(ref mut delay) => delay.poll().unwrap().is_ready(),
+                None => false
+            };
+            if elapsed {
+                self.attempt();
+                continue
+            }
+
+            if self.delay.is_none() && self.running.is_empty() {
+                return Err(self.last_error.take().expect("hedged attempt failed without an error"))
+            }
+            return Ok(Async::NotReady)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use futures::Future;
+    use futures_timer::Delay;
+    use action::{RunContext, WithRunContext};
+    use super::Strategy;
+
+    #[test]
+    fn hedges_a_slow_attempt_with_a_faster_one() {
+        let s = Strategy::fixed(Duration::from_millis(50))
+            .with_max_retries(1);
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry_hedged(|| {
+                num_calls += 1;
+                let (latency, item) = if num_calls == 1 { (500, 1) } else { (10, 2) };
+                Delay::new(Duration::from_millis(latency)).then(move |_| Ok::<u64, u64>(item))
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Ok(2));
+        assert_eq!(num_calls, 2);
+    }
+
+    #[test]
+    fn passes_the_run_context_to_each_attempt() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let mut contexts = Vec::new();
+        let res = {
+            let fut = s.retry_hedged(WithRunContext(|context: RunContext| {
+                contexts.push((context.attempt(), context.is_last()));
+                Err::<(), u64>(42)
+            }));
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(42));
+        assert_eq!(contexts, vec![(0, false), (1, false), (2, true)]);
+    }
+
+    #[test]
+    fn resolves_with_the_last_error_when_all_attempts_fail() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.retry_hedged(|| {
+                num_calls += 1;
+                Err::<(), u64>(num_calls)
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(3));
+        assert_eq!(num_calls, 3);
+    }
+}
@@ -35,19 +35,63 @@
 //! }
 //! ```
 
+#[macro_use]
 extern crate futures;
 extern crate futures_timer;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
 extern crate rand;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(test)]
+extern crate serde_json;
+#[cfg(feature = "tower")]
+extern crate tower_retry;
+#[cfg(all(test, feature = "tower"))]
+extern crate tower_service;
 
 mod action;
+mod error;
 mod condition;
+mod driver;
 mod strategy;
+mod stream;
 mod future;
+mod unwind;
+mod handle;
+mod hedged;
+mod retry_after;
+mod any;
+mod observer;
+mod router;
+mod probe;
+mod buffered;
+mod dynamic;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tower")]
+mod tower;
 
-pub use action::Action;
-pub use condition::Condition;
-pub use strategy::Strategy;
-pub use future::{Retry, RetryIf};
+pub use action::{sync_fn, Action, ByRef, FirstItem, Idempotent, IdempotentAction, OrElse, RunContext, StreamFirst, SyncFn, WithRunContext};
+pub use error::{RetryError, ScheduleError};
+pub use driver::{SleepDriver, TimerDriver};
+pub use condition::{is_retryable, And, Condition, Decision, Elapsed, Not, Or, PerCategory, RetryDecision};
+pub use strategy::{BudgetedDelays, Delays, RandomSource, Strategy, ThreadRandomSource};
+pub use stream::{backoff_stream, BackoffStream};
+pub use future::{Retry, RetryIf, RetryOrElse, RetryRich, RetryTimed, RetryTimeout, RetryUntil, RetryWithContext, Timing};
+pub use handle::RetryHandle;
+pub use hedged::RetryHedged;
+pub use unwind::CatchUnwindRetry;
+pub use retry_after::RetryAfter;
+pub use any::{retry_any, RetryAny};
+pub use observer::{Observer, Outcome, RetryObserved};
+pub use router::{RetryRouted, Router};
+pub use probe::RetryWithProbe;
+pub use buffered::RetryBuffered;
+pub use dynamic::{BoxAction, BoxFuture, RetryDriver};
+#[cfg(feature = "tower")]
+pub use tower::{TowerPolicy, TowerPolicyFuture};
 
 /// Run the given action, and retry on failure.
 ///
@@ -105,3 +149,60 @@ pub fn retry_if<A: Action, C>(action: A, condition: C) -> RetryIf<A, C>
 {
     Strategy::default().retry_if(action, condition)
 }
+
+/// Run the given action, and use the given strategy to retry on failure.
+///
+/// This is equivalent to [`Strategy::retry`](./struct.Strategy.html#method.retry).
+/// To also use a custom sleep driver, see [`Strategy::retry_with`](./struct.Strategy.html#method.retry_with).
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::time::Duration;
+/// # use futures::{Future, future};
+/// # use futures_backoff::{retry_with, Strategy};
+/// #
+/// # fn main() {
+/// let future = retry_with(&Strategy::fibonacci(Duration::from_millis(100)), || {
+///     // do some real-world stuff here...
+///     future::ok::<u32, ::std::io::Error>(42)
+/// });
+/// #
+/// # assert_eq!(future.wait().unwrap(), 42);
+/// # }
+/// ```
+pub fn retry_with<A: Action>(strategy: &Strategy, action: A) -> Retry<A> {
+    strategy.retry(action)
+}
+
+/// Run the given action, and use the given strategy to retry on failure if the error
+/// satisfies a given condition.
+///
+/// This is equivalent to [`Strategy::retry_if`](./struct.Strategy.html#method.retry_if).
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::io::{Error, ErrorKind};
+/// # use std::time::Duration;
+/// # use futures::{Future, future};
+/// # use futures_backoff::{retry_if_with, Strategy};
+/// #
+/// # fn main() {
+/// let future = retry_if_with(&Strategy::fixed(Duration::from_millis(100)), || {
+///     // do some real-world stuff here...
+///     future::ok(42)
+/// }, |err: &Error| err.kind() == ErrorKind::TimedOut);
+/// #
+/// # assert_eq!(future.wait().unwrap(), 42);
+/// # }
+/// ```
+pub fn retry_if_with<A: Action, C>(strategy: &Strategy, action: A, condition: C) -> RetryIf<A, C>
+    where C: Condition<A::Error>
+{
+    strategy.retry_if(action, condition)
+}
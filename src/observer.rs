@@ -0,0 +1,266 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future, Poll};
+
+use super::action::{Action, RunContext};
+use super::driver::{SleepDriver, TimerDriver};
+use super::future::Retry;
+use super::strategy::Strategy;
+
+/// The outcome of an attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The attempt succeeded.
+    Success,
+    /// The attempt failed.
+    Failure
+}
+
+/// Receives events about the attempts of a retry future.
+///
+/// Attempts are identified by their index, starting at zero for the initial attempt.
+/// All methods do nothing by default.
+pub trait Observer {
+    /// Called when an attempt is started.
+    fn on_attempt_start(&mut self, attempt: usize) {
+        let _ = attempt;
+    }
+
+    /// Called when an attempt has finished, along with the time it took.
+    fn on_attempt_end(&mut self, attempt: usize, outcome: Outcome, latency: Duration) {
+        let _ = (attempt, outcome, latency);
+    }
+
+    /// Called when the retry future starts sleeping after the given failed attempt.
+    fn on_sleep(&mut self, attempt: usize, delay: Duration) {
+        let _ = (attempt, delay);
+    }
+}
+
+impl<O: Observer> Observer for &mut O {
+    fn on_attempt_start(&mut self, attempt: usize) {
+        (**self).on_attempt_start(attempt)
+    }
+
+    fn on_attempt_end(&mut self, attempt: usize, outcome: Outcome, latency: Duration) {
+        (**self).on_attempt_end(attempt, outcome, latency)
+    }
+
+    fn on_sleep(&mut self, attempt: usize, delay: Duration) {
+        (**self).on_sleep(attempt, delay)
+    }
+}
+
+/// Observer shared between the action and the driver, along with the current attempt.
+struct Shared<O> {
+    observer: O,
+    attempt: usize
+}
+
+type SharedObserver<O> = Arc<Mutex<Shared<O>>>;
+
+struct ObservedAction<A, O> {
+    action: A,
+    shared: SharedObserver<O>,
+    runs: usize
+}
+
+impl<A: Action, O: Observer> ObservedAction<A, O> {
+    /// Notifies the observer about the given attempt, and starts it using `run`.
+    fn start<R>(&mut self, attempt: usize, run: R) -> ObservedFuture<A::Future, O>
+        where R: FnOnce(&mut A) -> A::Future
+    {
+        self.runs = attempt + 1;
+        {
+            let mut shared = self.shared.lock().unwrap();
+            shared.attempt = attempt;
+            shared.observer.on_attempt_start(attempt);
+        }
+        ObservedFuture {
+            future: run(&mut self.action),
+            shared: self.shared.clone(),
+            attempt,
+            started: Instant::now()
+        }
+    }
+}
+
+impl<A: Action, O: Observer> Action for ObservedAction<A, O> {
+    type Item = A::Item;
+    type Error = A::Error;
+    type Future = ObservedFuture<A::Future, O>;
+
+    fn run(&mut self) -> Self::Future {
+        let attempt = self.runs;
+        self.start(attempt, |action| action.run())
+    }
+
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        self.start(context.attempt(), |action| action.run_with_context(context))
+    }
+}
+
+struct ObservedFuture<F, O> {
+    future: F,
+    shared: SharedObserver<O>,
+    attempt: usize,
+    started: Instant
+}
+
+impl<F: Future, O: Observer> Future for ObservedFuture<F, O> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let (result, outcome) = match self.future.poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            result @ Ok(_) => (result, Outcome::Success),
+            result @ Err(_) => (result, Outcome::Failure)
+        };
+        let latency = self.started.elapsed();
+        self.shared.lock().unwrap().observer.on_attempt_end(self.attempt, outcome, latency);
+        result
+    }
+}
+
+struct ObservedDriver<D, O> {
+    driver: D,
+    shared: SharedObserver<O>
+}
+
+impl<D, O: Observer> ObservedDriver<D, O> {
+    fn observe(&self, duration: Duration) {
+        let mut shared = self.shared.lock().unwrap();
+        let attempt = shared.attempt;
+        shared.observer.on_sleep(attempt, duration);
+    }
+}
+
+impl<D: SleepDriver, O: Observer> SleepDriver for ObservedDriver<D, O> {
+    type Sleep = D::Sleep;
+
+    fn sleep(&self, duration: Duration) -> D::Sleep {
+        self.observe(duration);
+        self.driver.sleep(duration)
+    }
+
+    fn reset(&self, sleep: &mut D::Sleep, duration: Duration) {
+        self.observe(duration);
+        self.driver.reset(sleep, duration)
+    }
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, reporting
+/// every attempt and sleep to an [`Observer`](./trait.Observer.html).
+pub struct RetryObserved<A, O, D = TimerDriver> where A: Action, O: Observer, D: SleepDriver {
+    retry: Retry<ObservedAction<A, O>, ObservedDriver<D, O>>
+}
+
+impl<A: Action, O: Observer> RetryObserved<A, O> {
+    /// Creates a new retry future.
+    pub fn new(strategy: &Strategy, action: A, observer: O) -> RetryObserved<A, O> {
+        RetryObserved::new_with_driver(TimerDriver::default(), strategy, action, observer)
+    }
+}
+
+impl<A: Action, O: Observer, D: SleepDriver> RetryObserved<A, O, D> {
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(driver: D, strategy: &Strategy, action: A, observer: O) -> RetryObserved<A, O, D> {
+        let shared = Arc::new(Mutex::new(Shared {
+            observer,
+            attempt: 0
+        }));
+        let driver = ObservedDriver {
+            driver,
+            shared: shared.clone()
+        };
+        let action = ObservedAction {
+            action,
+            shared,
+            runs: 0
+        };
+        RetryObserved {
+            retry: Retry::new_with_driver(driver, strategy, action)
+        }
+    }
+}
+
+impl<A: Action, O: Observer, D: SleepDriver> fmt::Debug for RetryObserved<A, O, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryObserved").finish()
+    }
+}
+
+impl<A: Action, O: Observer, D: SleepDriver> Future for RetryObserved<A, O, D> {
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.retry.poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use futures::Future;
+    use super::{Observer, Outcome, Strategy};
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Start(usize),
+        End(usize, Outcome),
+        Sleep(usize, Duration)
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<Event>,
+        latencies: Vec<Duration>
+    }
+
+    impl Observer for Recorder {
+        fn on_attempt_start(&mut self, attempt: usize) {
+            self.events.push(Event::Start(attempt));
+        }
+
+        fn on_attempt_end(&mut self, attempt: usize, outcome: Outcome, latency: Duration) {
+            self.events.push(Event::End(attempt, outcome));
+            self.latencies.push(latency);
+        }
+
+        fn on_sleep(&mut self, attempt: usize, delay: Duration) {
+            self.events.push(Event::Sleep(attempt, delay));
+        }
+    }
+
+    #[test]
+    fn reports_attempts_and_sleeps() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let mut recorder = Recorder::default();
+        let mut calls = 0;
+        let res = s.retry_observed(|| {
+            calls += 1;
+            if calls < 3 {
+                Err::<&str, u64>(42)
+            } else {
+                Ok::<&str, u64>("done")
+            }
+        }, &mut recorder).wait();
+
+        assert_eq!(res, Ok("done"));
+        assert_eq!(recorder.events, vec![
+            Event::Start(0),
+            Event::End(0, Outcome::Failure),
+            Event::Sleep(0, Duration::from_millis(10)),
+            Event::Start(1),
+            Event::End(1, Outcome::Failure),
+            Event::Sleep(1, Duration::from_millis(10)),
+            Event::Start(2),
+            Event::End(2, Outcome::Success)
+        ]);
+        assert_eq!(recorder.latencies.len(), 3);
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt;
+use std::time::Instant;
+
+use futures::{Async, Future, IntoFuture, Poll};
+
+use super::action::{Action, RunContext};
+use super::driver::{SleepDriver, TimerDriver};
+use super::error::RetryError;
+use super::strategy::{Strategy, StrategyIter};
+
+enum ProbeState<A, T, D> where A: Action, T: IntoFuture, D: SleepDriver {
+    Probing(T::Future),
+    Running(A::Future),
+    Sleeping(D::Sleep)
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, running
+/// a probe before every attempt.
+///
+/// The action is only run if the probe resolves with `true`. Otherwise, or if the probe
+/// fails, the attempt counts as failed and is retried after the next delay.
+///
+/// The [`RunContext`](./struct.RunContext.html) that the action receives only counts
+/// the runs of the action, so attempts with a failed probe are not numbered.
+///
+/// If the probe of the last attempt does not succeed, the future resolves with
+/// [`RetryError::ProbeFailed`](./enum.RetryError.html#variant.ProbeFailed), unless
+/// the probe itself failed with an error.
+pub struct RetryWithProbe<A, P, T, D = TimerDriver>
+    where A: Action,
+          P: FnMut() -> T,
+          T: IntoFuture<Item=bool, Error=A::Error>,
+          D: SleepDriver
+{
+    strategy_iter: StrategyIter,
+    state: ProbeState<A, T, D>,
+    action: A,
+    probe: P,
+    driver: D,
+    started: Instant,
+    /// Number of times the action ran, not counting failed probes.
+    attempts: usize
+}
+
+impl<A, P, T> RetryWithProbe<A, P, T>
+    where A: Action,
+          P: FnMut() -> T,
+          T: IntoFuture<Item=bool, Error=A::Error>
+{
+    /// Creates a new retry future.
+    pub fn new(strategy: &Strategy, action: A, probe: P) -> RetryWithProbe<A, P, T> {
+        RetryWithProbe::new_with_driver(TimerDriver::default(), strategy, action, probe)
+    }
+}
+
+impl<A, P, T, D> RetryWithProbe<A, P, T, D>
+    where A: Action,
+          P: FnMut() -> T,
+          T: IntoFuture<Item=bool, Error=A::Error>,
+          D: SleepDriver
+{
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(driver: D, strategy: &Strategy, action: A, mut probe: P) -> RetryWithProbe<A, P, T, D> {
+        let future = probe().into_future();
+        RetryWithProbe {
+            strategy_iter: strategy.iter(),
+            state: ProbeState::Probing(future),
+            action,
+            probe,
+            driver,
+            started: Instant::now(),
+            attempts: 0
+        }
+    }
+
+    /// Moves on to the next attempt after a failure, or returns the error if the strategy is exhausted.
+    fn retry(&mut self, err: RetryError<A::Error>) -> Result<ProbeState<A, T, D>, RetryError<A::Error>> {
+        match self.strategy_iter.next() {
+            None => Err(err),
+            Some(duration) => Ok(ProbeState::Sleeping(self.driver.sleep(duration)))
+        }
+    }
+}
+
+impl<A, P, T, D> fmt::Debug for RetryWithProbe<A, P, T, D>
+    where A: Action,
+          P: FnMut() -> T,
+          T: IntoFuture<Item=bool, Error=A::Error>,
+          D: SleepDriver
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryWithProbe").finish()
+    }
+}
+
+impl<A, P, T, D> Future for RetryWithProbe<A, P, T, D>
+    where A: Action,
+          P: FnMut() -> T,
+          T: IntoFuture<Item=bool, Error=A::Error>,
+          D: SleepDriver
+{
+    type Item = A::Item;
+    type Error = RetryError<A::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let next = match self.state {
+                ProbeState::Probing(ref mut future) => match future.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(true)) => {
+                        let is_last = self.strategy_iter.is_exhausted();
+                        let elapsed = self.started.elapsed();
+                        let context = RunContext::new(self.attempts, is_last, elapsed);
+                        self.attempts += 1;
+                        ProbeState::Running(self.action.run_with_context(context))
+                    },
+                    Ok(Async::Ready(false)) => self.retry(RetryError::ProbeFailed)?,
+                    Err(err) => self.retry(RetryError::Operation(err))?
+                },
+                ProbeState::Running(ref mut future) => match future.poll() {
+                    Ok(async) => return Ok(async),
+                    Err(err) => self.retry(RetryError::Operation(err))?
+                },
+                ProbeState::Sleeping(ref mut sleep) => match sleep.poll().unwrap() {
+                    Async::NotReady => return Ok(Async::NotReady),
+                    Async::Ready(_) => ProbeState::Probing((self.probe)().into_future())
+                }
+            };
+            self.state = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::time::Duration;
+    use futures::Future;
+    use action::{RunContext, WithRunContext};
+    use super::super::error::RetryError;
+    use super::Strategy;
+
+    #[test]
+    fn runs_the_action_only_after_a_successful_probe() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let probes = Cell::new(0);
+        let actions = Cell::new(0);
+        let res = s.retry_with_probe(|| {
+            actions.set(actions.get() + 1);
+            assert_eq!(probes.get(), 2);
+            Ok::<&str, u64>("done")
+        }, || {
+            probes.set(probes.get() + 1);
+            Ok::<bool, u64>(probes.get() > 1)
+        }).wait();
+
+        assert_eq!(res, Ok("done"));
+        assert_eq!(probes.get(), 2);
+        assert_eq!(actions.get(), 1);
+    }
+
+    #[test]
+    fn probes_before_every_retry() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let probes = Cell::new(0);
+        let actions = Cell::new(0);
+        let res = s.retry_with_probe(|| {
+            actions.set(actions.get() + 1);
+            Err::<(), u64>(42)
+        }, || {
+            probes.set(probes.get() + 1);
+            Ok::<bool, u64>(true)
+        }).wait();
+
+        assert_eq!(res, Err(RetryError::Operation(42)));
+        assert_eq!(probes.get(), 3);
+        assert_eq!(actions.get(), 3);
+    }
+
+    #[test]
+    fn does_not_number_attempts_with_failed_probes() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(3);
+        let probes = Cell::new(0);
+        let attempts = RefCell::new(Vec::new());
+        let res = s.retry_with_probe(WithRunContext(|context: RunContext| {
+            attempts.borrow_mut().push(context.attempt());
+            Err::<(), u64>(42)
+        }), || {
+            probes.set(probes.get() + 1);
+            Ok::<bool, u64>(probes.get() != 2)
+        }).wait();
+
+        assert_eq!(res, Err(RetryError::Operation(42)));
+        assert_eq!(*attempts.borrow(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn fails_if_the_last_probe_fails() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let res = s.retry_with_probe(|| Ok::<(), u64>(()), || Ok::<bool, u64>(false)).wait();
+
+        assert_eq!(res, Err(RetryError::ProbeFailed));
+    }
+}
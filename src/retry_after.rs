@@ -0,0 +1,10 @@
+use std::time::Duration;
+
+/// An error that may suggest how long to wait before retrying.
+///
+/// This is useful for errors that carry a hint from the remote end,
+/// such as the `Retry-After` header of an HTTP response.
+pub trait RetryAfter {
+    /// Returns the suggested delay before the next attempt, if any.
+    fn retry_after(&self) -> Option<Duration>;
+}
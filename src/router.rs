@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+use std::time::Duration;
+
+use futures::{Future, Poll};
+
+use super::action::Action;
+use super::condition::Condition;
+use super::driver::{SleepDriver, TimerDriver};
+use super::future::RetryIf;
+use super::strategy::{Strategy, StrategyIter};
+
+/// Routes failures to different retry strategies, depending on the category of the error.
+///
+/// Created by [`Strategy::router`](./struct.Strategy.html#method.router).
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::collections::HashMap;
+/// # use std::io::{Error, ErrorKind};
+/// # use std::time::Duration;
+/// # use futures::{Future, future};
+/// # use futures_backoff::Strategy;
+/// #
+/// # fn main() {
+/// let mut strategies = HashMap::new();
+/// strategies.insert(ErrorKind::ConnectionReset, Strategy::fixed(Duration::from_millis(10)));
+/// strategies.insert(ErrorKind::WouldBlock, Strategy::exponential(Duration::from_secs(1)));
+///
+/// let router = Strategy::router(|err: &Error| err.kind(), strategies);
+///
+/// let future = router.retry(|| {
+///     // do some real-world stuff here...
+///     future::ok::<u32, Error>(42)
+/// });
+/// #
+/// # assert_eq!(future.wait().unwrap(), 42);
+/// # }
+/// ```
+pub struct Router<K, F> {
+    classify: F,
+    strategies: HashMap<K, Strategy>
+}
+
+impl<K: Eq + Hash + Clone, F: Clone> Router<K, F> {
+    pub(crate) fn new(classify: F, strategies: HashMap<K, Strategy>) -> Router<K, F> {
+        Router {
+            classify,
+            strategies
+        }
+    }
+
+    /// Run the given action, and use the strategy of each error's category to retry on failure.
+    pub fn retry<A: Action>(&self, action: A) -> RetryRouted<A, K, F>
+        where F: FnMut(&A::Error) -> K
+    {
+        self.retry_with(action, TimerDriver::default())
+    }
+
+    /// Run the given action, and use the strategy of each error's category to retry on failure.
+    ///
+    /// The given [`SleepDriver`](./trait.SleepDriver.html) is used to sleep between attempts.
+    pub fn retry_with<A: Action, D: SleepDriver>(&self, action: A, driver: D) -> RetryRouted<A, K, F, D>
+        where F: FnMut(&A::Error) -> K
+    {
+        let mut iters = self.strategies.iter()
+            .map(|(category, strategy)| (category.clone(), strategy.iter()))
+            .collect::<HashMap<_, _>>();
+        let active = iters.keys().next().cloned();
+        let strategy_iter = match active {
+            Some(ref category) => iters.remove(category).expect("category without strategy"),
+            None => Strategy::fixed(Duration::from_secs(0)).with_max_retries(0).iter()
+        };
+        let routes = Routes {
+            classify: self.classify.clone(),
+            iters,
+            active,
+            pending: None
+        };
+        RetryRouted {
+            retry_if: RetryIf::with_selection(driver, strategy_iter, action, routes, Routes::select, Routes::exhausted)
+        }
+    }
+}
+
+impl<K, F> fmt::Debug for Router<K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Router").finish()
+    }
+}
+
+/// Condition that retries the errors of categories with a strategy, and picks the
+/// strategy iterator of each error's category.
+///
+/// The iterator of the most recent category is owned by the retry future, the
+/// others wait in `iters` until their category comes up again.
+struct Routes<K, F> {
+    classify: F,
+    iters: HashMap<K, StrategyIter>,
+    active: Option<K>,
+    pending: Option<K>
+}
+
+impl<K: Eq + Hash, F> Routes<K, F> {
+    fn select(&mut self, strategy_iter: &mut StrategyIter) {
+        let category = match self.pending.take() {
+            Some(category) => category,
+            None => return
+        };
+        if self.active.as_ref() == Some(&category) {
+            return
+        }
+        let next = self.iters.remove(&category).expect("category without strategy");
+        let previous = mem::replace(strategy_iter, next);
+        if let Some(previous_category) = self.active.replace(category) {
+            self.iters.insert(previous_category, previous);
+        }
+    }
+
+    fn exhausted(&self, strategy_iter: &StrategyIter) -> bool {
+        (self.active.is_none() || strategy_iter.is_exhausted()) &&
+            self.iters.values().all(StrategyIter::is_exhausted)
+    }
+}
+
+impl<E, K: Eq + Hash, F: FnMut(&E) -> K> Condition<E> for Routes<K, F> {
+    fn should_retry(&mut self, error: &E) -> bool {
+        let category = (self.classify)(error);
+        let retry = self.active.as_ref() == Some(&category) || self.iters.contains_key(&category);
+        if retry {
+            self.pending = Some(category);
+        }
+        retry
+    }
+}
+
+/// Future that drives multiple attempts at an action, using the strategy of each error's
+/// category to retry on failure.
+///
+/// Every category keeps its own position in its strategy, so that switching between
+/// categories continues each schedule where it left off. Errors of categories without
+/// a strategy are not retried. Since the category of the next error is not known upfront,
+/// attempts are only told that they are the last one once all strategies are exhausted.
+///
+/// Created by [`Router::retry`](./struct.Router.html#method.retry).
+pub struct RetryRouted<A, K, F, D = TimerDriver>
+    where A: Action,
+          K: Eq + Hash,
+          F: FnMut(&A::Error) -> K,
+          D: SleepDriver
+{
+    retry_if: RetryIf<A, Routes<K, F>, D>
+}
+
+impl<A, K, F, D> fmt::Debug for RetryRouted<A, K, F, D>
+    where A: Action,
+          K: Eq + Hash,
+          F: FnMut(&A::Error) -> K,
+          D: SleepDriver
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryRouted").finish()
+    }
+}
+
+impl<A, K, F, D> Future for RetryRouted<A, K, F, D>
+    where A: Action,
+          K: Eq + Hash,
+          F: FnMut(&A::Error) -> K,
+          D: SleepDriver
+{
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.retry_if.poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io::Error;
+    use std::rc::Rc;
+    use std::time::Duration;
+    use futures::Future;
+    use futures::future::{self, FutureResult};
+    use super::super::{RunContext, SleepDriver, Strategy, WithRunContext};
+
+    #[derive(Clone, Default)]
+    struct Recording(Rc<RefCell<Vec<Duration>>>);
+
+    impl SleepDriver for Recording {
+        type Sleep = FutureResult<(), Error>;
+
+        fn sleep(&self, duration: Duration) -> Self::Sleep {
+            self.0.borrow_mut().push(duration);
+            future::ok(())
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Category {
+        Transient,
+        RateLimited,
+        Fatal
+    }
+
+    #[test]
+    fn uses_the_strategy_of_each_category() {
+        let mut strategies = HashMap::new();
+        strategies.insert(Category::Transient, Strategy::fixed(Duration::from_millis(10)));
+        strategies.insert(Category::RateLimited, Strategy::exponential(Duration::from_millis(20)));
+        let router = Strategy::router(|err: &Category| *err, strategies);
+        let driver = Recording::default();
+        let errors = vec![Category::Transient, Category::RateLimited, Category::Transient, Category::RateLimited];
+        let mut num_calls = 0;
+        let res = router.retry_with(|| {
+            num_calls += 1;
+            match errors.get(num_calls - 1) {
+                Some(&err) => Err(err),
+                None => Ok(num_calls)
+            }
+        }, driver.clone()).wait();
+
+        assert_eq!(res, Ok(5));
+        assert_eq!(*driver.0.borrow(), vec![
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+            Duration::from_millis(10),
+            Duration::from_millis(40)
+        ]);
+    }
+
+    #[test]
+    fn does_not_retry_categories_without_a_strategy() {
+        let mut strategies = HashMap::new();
+        strategies.insert(Category::Transient, Strategy::fixed(Duration::from_millis(10)));
+        let router = Strategy::router(|err: &Category| *err, strategies);
+        let mut num_calls = 0;
+        let res = router.retry_with(|| {
+            num_calls += 1;
+            Err::<(), Category>(Category::Fatal)
+        }, Recording::default()).wait();
+
+        assert_eq!(res, Err(Category::Fatal));
+        assert_eq!(num_calls, 1);
+    }
+
+    #[test]
+    fn is_last_once_all_strategies_are_exhausted() {
+        let mut strategies = HashMap::new();
+        strategies.insert(Category::Transient, Strategy::fixed(Duration::from_millis(10)).with_max_retries(1));
+        strategies.insert(Category::RateLimited, Strategy::fixed(Duration::from_millis(20)).with_max_retries(1));
+        let router = Strategy::router(|err: &Category| *err, strategies);
+        let errors = vec![Category::Transient, Category::RateLimited, Category::Transient];
+        let mut contexts = Vec::new();
+        let res = router.retry_with(WithRunContext(|context: RunContext| {
+            contexts.push(context.is_last());
+            Err::<(), Category>(errors[context.attempt()])
+        }), Recording::default()).wait();
+
+        assert_eq!(res, Err(Category::Transient));
+        assert_eq!(contexts, vec![false, false, true]);
+    }
+}
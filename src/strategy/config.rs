@@ -0,0 +1,183 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::ser::Error;
+
+use super::{FactorType, Strategy};
+
+/// Serializable representation of a strategy.
+///
+/// Durations are stored in milliseconds, so strategies with durations that are not
+/// whole milliseconds are refused. The random source is not part of the
+/// configuration, deserialized strategies use the default one.
+#[derive(Serialize, Deserialize)]
+struct Config {
+    #[serde(flatten)]
+    kind: Kind,
+    base_ms: u64,
+    max_delay_ms: Option<u64>,
+    max_retries: usize,
+    #[serde(default)]
+    max_total_delay_ms: Option<u64>,
+    #[serde(default)]
+    max_consecutive_failures: Option<usize>,
+    jitter: bool,
+    #[serde(default)]
+    absolute_jitter_ms: Option<(u64, u64)>,
+    #[serde(default)]
+    jitter_after_clamp: bool,
+    #[serde(default)]
+    skip_first_delay: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Kind {
+    Exponential,
+    ExponentialBounded { max_shift: u32 },
+    Fibonacci,
+    Fixed,
+    Delays { delays_ms: Vec<u64>, cycle: bool },
+    Multiplier { multiplier: f64 }
+}
+
+fn to_millis<E: Error>(duration: Duration) -> Result<u64, E> {
+    let millis = duration.as_secs()
+        .checked_mul(1000)
+        .and_then(|millis| millis.checked_add(u64::from(duration.subsec_millis())));
+    match millis {
+        Some(millis) if Duration::from_millis(millis) == duration => Ok(millis),
+        _ => Err(E::custom(format!("duration {:?} is not a whole number of milliseconds", duration)))
+    }
+}
+
+impl Serialize for Strategy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.delay_hook.is_some() {
+            return Err(S::Error::custom("a strategy with a delay hook cannot be serialized"))
+        }
+        let kind = match self.factor {
+            FactorType::Exponential => Kind::Exponential,
+            FactorType::ExponentialBounded(max_shift) => Kind::ExponentialBounded { max_shift },
+            FactorType::Fibonacci => Kind::Fibonacci,
+            FactorType::Fixed => Kind::Fixed,
+            FactorType::Function(_) =>
+                return Err(S::Error::custom("a strategy with a factor function cannot be serialized")),
+            FactorType::Delays(ref delays, cycle) => Kind::Delays {
+                delays_ms: delays.iter().cloned().map(to_millis).collect::<Result<_, _>>()?,
+                cycle
+            },
+            FactorType::Multiplier(multiplier) => Kind::Multiplier { multiplier }
+        };
+        Config {
+            kind,
+            base_ms: to_millis(self.delay)?,
+            max_delay_ms: self.max_delay.map(to_millis).transpose()?,
+            max_retries: self.max_retries,
+            max_total_delay_ms: self.max_total_delay.map(to_millis).transpose()?,
+            max_consecutive_failures: self.max_consecutive_failures,
+            jitter: self.jitter,
+            absolute_jitter_ms: match self.absolute_jitter {
+                Some((min, max)) => Some((to_millis(min)?, to_millis(max)?)),
+                None => None
+            },
+            jitter_after_clamp: self.jitter_after_clamp,
+            skip_first_delay: self.skip_first_delay
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Strategy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Strategy, D::Error> {
+        let config = Config::deserialize(deserializer)?;
+        let factor = match config.kind {
+            Kind::Exponential => FactorType::Exponential,
+            Kind::ExponentialBounded { max_shift } => FactorType::ExponentialBounded(max_shift),
+            Kind::Fibonacci => FactorType::Fibonacci,
+            Kind::Fixed => FactorType::Fixed,
+            Kind::Delays { delays_ms, cycle } =>
+                FactorType::Delays(Arc::new(delays_ms.into_iter().map(Duration::from_millis).collect()), cycle),
+            Kind::Multiplier { multiplier } => FactorType::Multiplier(multiplier)
+        };
+        let mut strategy = Strategy::new(factor, Duration::from_millis(config.base_ms));
+        strategy.max_delay = config.max_delay_ms.map(Duration::from_millis);
+        strategy.max_retries = config.max_retries;
+        strategy.max_total_delay = config.max_total_delay_ms.map(Duration::from_millis);
+        strategy.max_consecutive_failures = config.max_consecutive_failures;
+        strategy.jitter = config.jitter;
+        strategy.absolute_jitter = config.absolute_jitter_ms
+            .map(|(min, max)| (Duration::from_millis(min), Duration::from_millis(max)));
+        strategy.jitter_after_clamp = config.jitter_after_clamp;
+        strategy.skip_first_delay = config.skip_first_delay;
+        Ok(strategy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json;
+
+    use super::super::Strategy;
+
+    #[test]
+    fn round_trips_through_json() {
+        let s = Strategy::fibonacci(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(450))
+            .with_max_retries(7)
+            .skip_first_delay();
+        let json = serde_json::to_string(&s).unwrap();
+        let t: Strategy = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(t.schedule().unwrap(), s.schedule().unwrap());
+        assert_eq!(t.to_string(), s.to_string());
+    }
+
+    #[test]
+    fn uses_a_tagged_representation() {
+        let s = Strategy::exponential(Duration::from_millis(1000));
+        let value = serde_json::to_value(&s).unwrap();
+
+        assert_eq!(value["kind"], "exponential");
+        assert_eq!(value["base_ms"], 1000);
+        assert_eq!(value["max_delay_ms"], serde_json::Value::Null);
+        assert_eq!(value["max_retries"], 5);
+        assert_eq!(value["jitter"], false);
+    }
+
+    #[test]
+    fn deserializes_minimal_config() {
+        let s: Strategy = serde_json::from_str(
+            r#"{ "kind": "fixed", "base_ms": 10, "max_delay_ms": null, "max_retries": 3, "jitter": false }"#
+        ).unwrap();
+
+        assert_eq!(s.schedule().unwrap(), vec![Duration::from_millis(10); 3]);
+    }
+
+    #[test]
+    fn round_trips_explicit_delays() {
+        let s = Strategy::from_delays(&[Duration::from_millis(5), Duration::from_millis(50)]);
+        let t: Strategy = serde_json::from_str(&serde_json::to_string(&s).unwrap()).unwrap();
+
+        assert_eq!(t.schedule().unwrap(), s.schedule().unwrap());
+    }
+
+    #[test]
+    fn refuses_to_serialize_sub_millisecond_durations() {
+        let s = Strategy::fixed(Duration::from_micros(1500));
+        let t = Strategy::fixed(Duration::from_millis(2))
+            .with_max_delay(Duration::from_nanos(1));
+
+        assert!(serde_json::to_string(&s).is_err());
+        assert!(serde_json::to_string(&t).is_err());
+    }
+
+    #[test]
+    fn refuses_to_serialize_factor_functions() {
+        let s = Strategy::factor_fn(Duration::from_millis(10), |n| n);
+
+        assert!(serde_json::to_string(&s).is_err());
+    }
+}
@@ -0,0 +1,24 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub(crate) struct DelayHook(Arc<Mutex<dyn FnMut(usize, Duration) -> Duration + Send>>);
+
+impl DelayHook {
+    pub fn new<F: FnMut(usize, Duration) -> Duration + Send + 'static>(hook: F) -> DelayHook {
+        DelayHook(Arc::new(Mutex::new(hook)))
+    }
+
+    pub fn call(&self, index: usize, delay: Duration) -> Duration {
+        // A hook that panicked once is still called for later delays.
+        let mut hook = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        (*hook)(index, delay)
+    }
+}
+
+impl fmt::Debug for DelayHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DelayHook").finish()
+    }
+}
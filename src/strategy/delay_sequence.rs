@@ -0,0 +1,44 @@
+use std::iter::Iterator;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct DelaySequence {
+    delays: Arc<Vec<Duration>>,
+    index: usize,
+    cycle: bool
+}
+
+impl DelaySequence {
+    pub fn new(delays: Arc<Vec<Duration>>, cycle: bool) -> DelaySequence {
+        DelaySequence {
+            delays,
+            index: 0,
+            cycle
+        }
+    }
+}
+
+impl Iterator for DelaySequence {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        if self.index >= self.delays.len() {
+            if !self.cycle || self.delays.is_empty() {
+                return None
+            }
+            self.index = 0;
+        }
+        let delay = self.delays[self.index];
+        self.index += 1;
+        Some(delay)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.cycle && !self.delays.is_empty() {
+            return (usize::MAX, None)
+        }
+        let remaining = self.delays.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
@@ -1,17 +1,26 @@
 use std::iter::Iterator;
-use std::u32::MAX as U32_MAX;
 
 #[derive(Debug, Clone)]
 pub struct ExponentialBackoff {
     curr: u32,
-    base: u32
+    base: u32,
+    max: u32
 }
 
 impl ExponentialBackoff {
     pub fn new() -> ExponentialBackoff {
         ExponentialBackoff {
             curr: 1,
-            base: 2
+            base: 2,
+            max: u32::MAX
+        }
+    }
+
+    /// Stops growing once the factor reaches `2^max_shift`.
+    pub fn bounded(max_shift: u32) -> ExponentialBackoff {
+        ExponentialBackoff {
+            max: 1 << ::std::cmp::min(max_shift, 31),
+            ..ExponentialBackoff::new()
         }
     }
 }
@@ -23,9 +32,9 @@ impl Iterator for ExponentialBackoff {
         let factor = self.curr;
 
         if let Some(next) = self.curr.checked_mul(self.base) {
-            self.curr = next;
+            self.curr = ::std::cmp::min(next, self.max);
         } else {
-            self.curr = U32_MAX;
+            self.curr = self.max;
         }
 
         Some(factor)
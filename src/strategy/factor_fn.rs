@@ -0,0 +1,36 @@
+use std::fmt;
+use std::iter::Iterator;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct FactorFn {
+    f: Arc<dyn Fn(u32) -> u32 + Send + Sync>,
+    attempt: u32
+}
+
+impl FactorFn {
+    pub fn new(f: Arc<dyn Fn(u32) -> u32 + Send + Sync>) -> FactorFn {
+        FactorFn {
+            f,
+            attempt: 0
+        }
+    }
+}
+
+impl fmt::Debug for FactorFn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FactorFn")
+            .field("attempt", &self.attempt)
+            .finish()
+    }
+}
+
+impl Iterator for FactorFn {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let factor = (self.f)(self.attempt);
+        self.attempt = self.attempt.saturating_add(1);
+        Some(factor)
+    }
+}
@@ -1,6 +1,56 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use rand::{random, Closed01};
 
+/// A source of randomness used to apply jitter to delays.
+pub trait RandomSource {
+    /// Returns a random duration between zero and `max` (inclusive).
+    fn jitter(&mut self, max: Duration) -> Duration;
+}
+
+/// Random source that uses the thread-local random number generator.
+///
+/// This is the random source that strategies use by default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadRandomSource;
+
+impl RandomSource for ThreadRandomSource {
+    fn jitter(&mut self, max: Duration) -> Duration {
+        let Closed01(jitter) = random();
+        apply_jitter(max, jitter)
+    }
+}
+
+/// The random source of a strategy, shared between its iterators.
+///
+/// Only custom sources need a lock; the default source is stateless.
+#[derive(Clone, Default)]
+pub(crate) enum SharedRandomSource {
+    #[default]
+    Thread,
+    Custom(Arc<Mutex<dyn RandomSource + Send>>)
+}
+
+impl SharedRandomSource {
+    pub fn new<R: RandomSource + Send + 'static>(source: R) -> SharedRandomSource {
+        SharedRandomSource::Custom(Arc::new(Mutex::new(source)))
+    }
+
+    pub fn jitter(&self, max: Duration) -> Duration {
+        match *self {
+            SharedRandomSource::Thread => ThreadRandomSource.jitter(max),
+            SharedRandomSource::Custom(ref source) => source.lock().unwrap().jitter(max)
+        }
+    }
+}
+
+impl fmt::Debug for SharedRandomSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RandomSource").finish()
+    }
+}
+
 fn apply_jitter(duration: Duration, jitter: f64) -> Duration {
     let secs = (duration.as_secs() as f64) * jitter;
     let nanos = (duration.subsec_nanos() as f64) * jitter;
@@ -8,11 +58,6 @@ fn apply_jitter(duration: Duration, jitter: f64) -> Duration {
     Duration::from_millis(millis as u64)
 }
 
-pub fn jitter(duration: Duration) -> Duration {
-    let Closed01(jitter) = random();
-    apply_jitter(duration, jitter)
-}
-
 #[test]
 fn apply_jitter_quickcheck() {
     extern crate quickcheck;
@@ -1,22 +1,121 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
 
-use super::{Action, Condition, Retry, RetryIf};
+use std::panic::UnwindSafe;
+
+use futures::{IntoFuture, Stream};
+
+use super::{sync_fn, Action, ByRef, RetryUntil, ScheduleError, RetryBuffered, StreamFirst, RetryRich, RetryWithProbe, Router, Decision, RetryDecision, IdempotentAction, Observer, RetryObserved, CatchUnwindRetry, RetryTimed, RetryWithContext, SleepDriver, SyncFn, Condition, RetryHandle, Elapsed, Retry, RetryAfter, RetryHedged, RetryIf, RetryOrElse};
 
 mod fixed_interval;
 mod exponential_backoff;
 mod fibonacci_backoff;
+mod factor_fn;
+mod delay_sequence;
+mod multiplier_backoff;
 mod jitter;
+mod delay_hook;
+#[cfg(feature = "serde")]
+mod config;
 
 pub use self::fixed_interval::FixedInterval;
 pub use self::exponential_backoff::ExponentialBackoff;
 pub use self::fibonacci_backoff::FibonacciBackoff;
-pub use self::jitter::jitter;
+pub use self::factor_fn::FactorFn;
+pub use self::delay_sequence::DelaySequence;
+pub use self::multiplier_backoff::MultiplierBackoff;
+pub use self::jitter::{RandomSource, ThreadRandomSource};
+use self::jitter::SharedRandomSource;
+use self::delay_hook::DelayHook;
 
-#[derive(Debug)]
 enum FactorType {
     Exponential,
+    ExponentialBounded(u32),
     Fibonacci,
-    Fixed
+    Fixed,
+    Function(Arc<dyn Fn(u32) -> u32 + Send + Sync>),
+    Delays(Arc<Vec<Duration>>, bool),
+    Multiplier(f64)
+}
+
+impl fmt::Debug for FactorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FactorType::Exponential => f.write_str("Exponential"),
+            FactorType::ExponentialBounded(max_shift) =>
+                f.debug_tuple("ExponentialBounded").field(&max_shift).finish(),
+            FactorType::Fibonacci => f.write_str("Fibonacci"),
+            FactorType::Fixed => f.write_str("Fixed"),
+            FactorType::Function(_) => f.write_str("Function"),
+            FactorType::Delays(ref delays, cycle) =>
+                f.debug_tuple("Delays").field(delays).field(&cycle).finish(),
+            FactorType::Multiplier(multiplier) =>
+                f.debug_tuple("Multiplier").field(&multiplier).finish()
+        }
+    }
+}
+
+impl fmt::Display for FactorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FactorType::Exponential => f.write_str("exponential"),
+            FactorType::ExponentialBounded(max_shift) => write!(f, "exponential max_shift={}", max_shift),
+            FactorType::Fibonacci => f.write_str("fibonacci"),
+            FactorType::Fixed => f.write_str("fixed"),
+            FactorType::Function(_) => f.write_str("function"),
+            FactorType::Delays(_, false) => f.write_str("delays"),
+            FactorType::Delays(_, true) => f.write_str("cycled delays"),
+            FactorType::Multiplier(multiplier) => write!(f, "exponential x{}", multiplier)
+        }
+    }
+}
+
+/// Delay used in place of deadlines that are too far in the future to be represented.
+const FAR_FUTURE: Duration = Duration::from_secs(86400 * 365 * 30);
+
+/// Returns the instant at which `delay` has elapsed after `start`.
+///
+/// Saturates far in the future instead of overflowing.
+pub(crate) fn deadline(start: Instant, delay: Duration) -> Instant {
+    start.checked_add(delay).unwrap_or_else(|| start + FAR_FUTURE)
+}
+
+/// Maximum number of delays collected by `Strategy::schedule`.
+const SCHEDULE_LIMIT: usize = 10000;
+
+/// Maximum number of delays shown when formatting a strategy.
+const FORMAT_SCHEDULE_LIMIT: usize = 10;
+
+/// Formats the delays of a strategy, without jitter or the delay hook applied.
+struct Schedule<'a>(&'a Strategy);
+
+impl<'a> fmt::Display for Schedule<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut iter = self.0.iter();
+        iter.jitter = false;
+        iter.absolute_jitter = None;
+        // Formatting must not call the hook, since that could change its state.
+        iter.delay_hook = None;
+        #[cfg(feature = "log")]
+        {
+            iter.clamp_warned = true;
+        }
+        f.write_str("[")?;
+        for (i, delay) in iter.by_ref().take(FORMAT_SCHEDULE_LIMIT).enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{:?}", delay)?;
+        }
+        if iter.next().is_some() {
+            f.write_str(", ...")?;
+        }
+        f.write_str("]")
+    }
 }
 
 /// Configurable retry strategy.
@@ -44,13 +143,28 @@ enum FactorType {
 /// # assert_eq!(future.wait().unwrap(), 42);
 /// # }
 /// ```
-#[derive(Debug)]
+///
+/// The `Display` implementation renders the configuration along with the
+/// resulting delays, for example `exponential base=1s max_delay=none retries=5 jitter=off -> [1s, 2s, 4s, 8s, 16s]`.
+/// When jitter or a delay hook (see [`with_delay_hook`](#method.with_delay_hook)) is set,
+/// the delays are shown before either is applied.
+///
+/// With the `serde` feature enabled, strategies can be serialized to and deserialized
+/// from a configuration like `{ "kind": "exponential", "base_ms": 1000, "max_delay_ms": null, "max_retries": 5, "jitter": false }`.
+/// Strategies created with [`factor_fn`](#method.factor_fn) or with a delay hook cannot be serialized.
 pub struct Strategy {
     factor: FactorType,
     delay: Duration,
     max_delay: Option<Duration>,
     max_retries: usize,
-    jitter: bool
+    max_total_delay: Option<Duration>,
+    max_consecutive_failures: Option<usize>,
+    jitter: bool,
+    absolute_jitter: Option<(Duration, Duration)>,
+    jitter_after_clamp: bool,
+    random_source: SharedRandomSource,
+    delay_hook: Option<DelayHook>,
+    skip_first_delay: bool
 }
 
 impl Default for Strategy {
@@ -60,8 +174,66 @@ impl Default for Strategy {
             delay: Duration::from_millis(1000),
             max_delay: None,
             max_retries: 5,
-            jitter: false
+            max_total_delay: None,
+            max_consecutive_failures: None,
+            jitter: false,
+            absolute_jitter: None,
+            jitter_after_clamp: false,
+            random_source: SharedRandomSource::default(),
+            delay_hook: None,
+            skip_first_delay: false
+        }
+    }
+}
+
+impl fmt::Debug for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Strategy")
+            .field("factor", &self.factor)
+            .field("delay", &self.delay)
+            .field("max_delay", &self.max_delay)
+            .field("max_retries", &self.max_retries)
+            .field("max_total_delay", &self.max_total_delay)
+            .field("max_consecutive_failures", &self.max_consecutive_failures)
+            .field("jitter", &self.jitter)
+            .field("absolute_jitter", &self.absolute_jitter)
+            .field("jitter_after_clamp", &self.jitter_after_clamp)
+            .field("random_source", &self.random_source)
+            .field("delay_hook", &self.delay_hook)
+            .field("skip_first_delay", &self.skip_first_delay)
+            .field("schedule", &format_args!("{}", Schedule(self)))
+            .finish()
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} base={:?} ", self.factor, self.delay)?;
+        match self.max_delay {
+            Some(max_delay) => write!(f, "max_delay={:?} ", max_delay)?,
+            None => f.write_str("max_delay=none ")?
         }
+        write!(f, "retries={} jitter={} -> {}",
+            self.max_retries, if self.has_jitter() { "on" } else { "off" }, Schedule(self))
+    }
+}
+
+impl IntoIterator for &Strategy {
+    type Item = Duration;
+    type IntoIter = BudgetedDelays;
+
+    fn into_iter(self) -> BudgetedDelays {
+        self.budgeted_delays()
+    }
+}
+
+impl From<Duration> for Strategy {
+    /// Creates a retry strategy driven by exponential back-off,
+    /// using the given duration as the base delay.
+    ///
+    /// This is equivalent to [`Strategy::exponential`](#method.exponential).
+    fn from(delay: Duration) -> Strategy {
+        Strategy::exponential(delay)
     }
 }
 
@@ -74,6 +246,28 @@ impl Strategy {
         Strategy::new(FactorType::Exponential, delay)
     }
 
+    /// Creates a retry strategy driven by exponential back-off, which stops growing after a number of doublings.
+    ///
+    /// The specified duration will be multiplied by `2^min(n, max_shift)`, where `n` is
+    /// the number of failed attempts minus one. Unlike [`exponential`](#method.exponential),
+    /// whose factor saturates once it overflows, this plateaus at a predictable delay.
+    /// Shifts beyond 31 are treated as 31.
+    pub fn exponential_bounded(delay: Duration, max_shift: u32) -> Strategy {
+        Strategy::new(FactorType::ExponentialBounded(max_shift), delay)
+    }
+
+    /// Creates a retry strategy driven by exponential back-off with an arbitrary multiplier.
+    ///
+    /// The first retry waits for `initial`, and every following delay is `multiplier`
+    /// times the previous one, limited to `max_delay`. This matches the parameterization
+    /// of the `ExponentialBackoff` in the [`backoff`](https://crates.io/crates/backoff) crate,
+    /// without randomization (see [`with_jitter`](#method.with_jitter)).
+    pub fn exponential_full(initial: Duration, multiplier: f64, max_delay: Duration, max_retries: usize) -> Strategy {
+        Strategy::new(FactorType::Multiplier(multiplier), initial)
+            .with_max_delay(max_delay)
+            .with_max_retries(max_retries)
+    }
+
     /// Creates a retry strategy driven by a fibonacci back-off.
     ///
     /// The specified duration will be multiplied by `fib(n)`, where `n` is
@@ -94,18 +288,72 @@ impl Strategy {
         Strategy::new(FactorType::Fixed, delay)
     }
 
+    /// Creates a retry strategy driven by a custom factor function.
+    ///
+    /// The specified duration will be multiplied by `f(n)`, where `n` is
+    /// the number of failed attempts minus one, i.e. `f(0)` is used for the first retry.
+    pub fn factor_fn<F>(delay: Duration, f: F) -> Strategy
+        where F: Fn(u32) -> u32 + Send + Sync + 'static
+    {
+        Strategy::new(FactorType::Function(Arc::new(f)), delay)
+    }
+
+    /// Creates a retry strategy that uses exactly the given delays, in order.
+    ///
+    /// Once all delays have been used, no further retries are attempted.
+    /// This is useful to replay a captured schedule.
+    pub fn from_delays(delays: &[Duration]) -> Strategy {
+        Strategy::explicit_delays(delays, false)
+            .with_max_retries(delays.len())
+    }
+
+    /// Creates a retry strategy that uses the given delays, in order,
+    /// starting over once all delays have been used.
+    pub fn cycle_delays(delays: &[Duration]) -> Strategy {
+        Strategy::explicit_delays(delays, true)
+    }
+
+    fn explicit_delays(delays: &[Duration], cycle: bool) -> Strategy {
+        let base = delays.first().cloned().unwrap_or_default();
+        Strategy::new(FactorType::Delays(Arc::new(delays.to_vec()), cycle), base)
+    }
+
     fn new(factor: FactorType, delay: Duration) -> Strategy {
         Strategy {
             factor: factor,
             delay: delay,
             max_delay: None,
             max_retries: 5,
-            jitter: false
+            max_total_delay: None,
+            max_consecutive_failures: None,
+            jitter: false,
+            absolute_jitter: None,
+            jitter_after_clamp: false,
+            random_source: SharedRandomSource::default(),
+            delay_hook: None,
+            skip_first_delay: false
         }
     }
 
+    /// Creates a router that retries each category of errors with its own strategy.
+    ///
+    /// Every error is mapped to a category by the given function, and the next delay
+    /// is taken from the strategy of that category. Errors of categories without a
+    /// strategy are not retried.
+    pub fn router<K, F>(classify: F, strategies: HashMap<K, Strategy>) -> Router<K, F>
+        where K: Eq + Hash + Clone,
+              F: Clone
+    {
+        Router::new(classify, strategies)
+    }
+
     /// Sets the maximum delay between two attempts.
     ///
+    /// With the `log` feature enabled, a warning is logged the first time a delay is limited
+    /// to the maximum, since that can indicate a misconfigured base delay or number of retries.
+    /// The warning is logged once per sequence of delays, so every retry future (and every
+    /// iterator over the delays) created from this strategy may log it again.
+    ///
     /// By default there is no maximum.
     pub fn with_max_delay(mut self, duration: Duration) -> Self {
         self.max_delay = Some(duration);
@@ -120,31 +368,217 @@ impl Strategy {
         self
     }
 
+    /// Sets the maximum sum of all delays.
+    ///
+    /// No further retry is attempted once the next delay would exceed this budget.
+    /// The budget accounts for the actual delays, after jitter has been applied.
+    ///
+    /// By default there is no maximum.
+    pub fn with_max_total_delay(mut self, duration: Duration) -> Self {
+        self.max_total_delay = Some(duration);
+        self
+    }
+
+    /// Sets the maximum number of consecutive failures.
+    ///
+    /// Unlike [`with_max_retries`](#method.with_max_retries), this limits the failures in a row,
+    /// so it only makes a difference in loops that keep going after a success,
+    /// such as one driven by a [`BackoffStream`](./struct.BackoffStream.html).
+    /// Resetting the stream after a success clears the streak.
+    ///
+    /// By default there is no maximum.
+    pub fn with_max_consecutive_failures(mut self, failures: usize) -> Self {
+        self.max_consecutive_failures = Some(failures);
+        self
+    }
+
     /// Enables or disables jitter on the delay.
     ///
     /// Jitter will introduce a random variance to the retry strategy,
     /// which can be helpful to mitigate the "Thundering Herd" problem.
+    /// Each delay is replaced with a random duration between zero and the delay.
+    ///
+    /// By default jitter is applied before the maximum delay, see
+    /// [`with_jitter_after_clamp`](#method.with_jitter_after_clamp).
     pub fn with_jitter(mut self, jitter: bool) -> Self {
         self.jitter = jitter;
         self
     }
 
+    /// Adds a random duration between `min` and `max` (inclusive) to each delay.
+    ///
+    /// Unlike [`with_jitter`](#method.with_jitter), the added duration does not depend on the delay.
+    /// The maximum delay is still respected after the jitter has been added, unless
+    /// [`with_jitter_after_clamp`](#method.with_jitter_after_clamp) is enabled.
+    /// If `max` is less than `min`, exactly `min` is added.
+    pub fn with_absolute_jitter(mut self, min: Duration, max: Duration) -> Self {
+        self.absolute_jitter = Some((min, max));
+        self
+    }
+
+    /// Chooses whether jitter is applied before or after limiting delays to the maximum delay.
+    ///
+    /// By default jitter is applied first, and the jittered delay is then limited to the
+    /// maximum delay. Once the delays grow beyond the maximum, most jittered delays are
+    /// cut off at the maximum, so the delays pile up there. When jitter is applied after
+    /// the maximum delay instead, the delays are spread evenly below the maximum, and
+    /// absolute jitter may exceed it.
+    pub fn with_jitter_after_clamp(mut self, after_clamp: bool) -> Self {
+        self.jitter_after_clamp = after_clamp;
+        self
+    }
+
+    fn has_jitter(&self) -> bool {
+        self.jitter || self.absolute_jitter.is_some()
+    }
+
+    /// Sets the random source used to apply jitter.
+    ///
+    /// By default the thread-local random number generator is used.
+    /// A custom source can be used to make jitter reproducible, or to
+    /// apply a different distribution.
+    pub fn with_random_source<R: RandomSource + Send + 'static>(mut self, source: R) -> Self {
+        self.random_source = SharedRandomSource::new(source);
+        self
+    }
+
+    /// Sets a hook that decides on the delay that is actually used.
+    ///
+    /// The hook receives the index of the delay, starting at zero for the delay after
+    /// the initial attempt, and the delay as computed by this strategy, including jitter
+    /// and the maximum delay. The delay that the hook returns is used as-is, and counts
+    /// against the total delay budget. This allows to implement any custom jitter or clamping.
+    ///
+    /// A single instance of the hook is shared by every retry future, every iterator
+    /// over the delays and every call to [`schedule`](#method.schedule) of this strategy,
+    /// so any state that the hook keeps is shared between them as well. Inspecting the delays
+    /// via [`schedule`](#method.schedule), [`delays`](#method.delays) or
+    /// [`budgeted_delays`](#method.budgeted_delays) calls the hook; formatting the strategy does not.
+    pub fn with_delay_hook<F>(mut self, hook: F) -> Self
+        where F: FnMut(usize, Duration) -> Duration + Send + 'static
+    {
+        self.delay_hook = Some(DelayHook::new(hook));
+        self
+    }
+
+    /// Skips the first delay of the back-off schedule.
+    ///
+    /// The first retry will use what would otherwise be the second delay,
+    /// which is useful when the first try happens outside of this strategy.
+    /// The number of retry attempts is not affected.
+    pub fn skip_first_delay(mut self) -> Self {
+        self.skip_first_delay = true;
+        self
+    }
+
+    /// Scales all delays of this strategy by the given factor.
+    ///
+    /// This multiplies the base delay, the explicitly given delays, the maximum delay,
+    /// the total delay budget and the absolute jitter. Delays saturate instead of overflowing,
+    /// and negative factors result in zero delays.
+    pub fn scaled(mut self, factor: f64) -> Self {
+        self.delay = scale(self.delay, factor);
+        if let FactorType::Delays(ref mut delays, _) = self.factor {
+            *delays = Arc::new(delays.iter().map(|&delay| scale(delay, factor)).collect());
+        }
+        self.max_delay = self.max_delay.map(|delay| scale(delay, factor));
+        self.max_total_delay = self.max_total_delay.map(|delay| scale(delay, factor));
+        self.absolute_jitter = self.absolute_jitter.map(|(min, max)| (scale(min, factor), scale(max, factor)));
+        self
+    }
+
     pub(crate) fn iter(&self) -> StrategyIter {
-        let factor_iter = match self.factor {
+        let mut factor_iter = match self.factor {
             FactorType::Exponential =>
                 FactorIter::Exponential(ExponentialBackoff::new()),
+            FactorType::ExponentialBounded(max_shift) =>
+                FactorIter::Exponential(ExponentialBackoff::bounded(max_shift)),
             FactorType::Fibonacci =>
                 FactorIter::Fibonacci(FibonacciBackoff::new()),
             FactorType::Fixed =>
-                FactorIter::Fixed(FixedInterval::new())
+                FactorIter::Fixed(FixedInterval::new()),
+            FactorType::Function(ref f) =>
+                FactorIter::Function(FactorFn::new(f.clone())),
+            FactorType::Delays(ref delays, cycle) =>
+                FactorIter::Delays(DelaySequence::new(delays.clone(), cycle)),
+            FactorType::Multiplier(multiplier) =>
+                FactorIter::Multiplier(MultiplierBackoff::new(multiplier))
         };
+        if self.skip_first_delay {
+            factor_iter.next_delay(self.delay);
+        }
         StrategyIter {
             factor_iter: factor_iter,
             delay: self.delay,
             max_delay: self.max_delay,
             retries: self.max_retries,
-            jitter: self.jitter
+            max_total_delay: self.max_total_delay,
+            total_delay: Duration::from_secs(0),
+            max_consecutive_failures: self.max_consecutive_failures,
+            consecutive_failures: 0,
+            jitter: self.jitter,
+            absolute_jitter: self.absolute_jitter,
+            jitter_after_clamp: self.jitter_after_clamp,
+            random_source: self.random_source.clone(),
+            delay_hook: self.delay_hook.clone(),
+            index: 0,
+            peeked: None,
+            #[cfg(feature = "log")]
+            clamp_warned: false
+        }
+    }
+
+    /// Returns the delays between attempts that this strategy produces.
+    ///
+    /// If jitter is enabled, the delays are randomized anew on every call.
+    ///
+    /// Fails with `ScheduleError::Unbounded` instead of collecting more than 10000 delays.
+    /// Use [`budgeted_delays`](#method.budgeted_delays) to lazily iterate over the delays of such strategies.
+    ///
+    /// If a delay hook is set (see [`with_delay_hook`](#method.with_delay_hook)), it is called
+    /// for every delay, just like during a retry, so any state that it keeps advances as well.
+    pub fn schedule(&self) -> Result<Vec<Duration>, ScheduleError> {
+        let delays = self.iter();
+        if delays.size_hint().0 > SCHEDULE_LIMIT {
+            return Err(ScheduleError::Unbounded)
+        }
+        let schedule = delays.take(SCHEDULE_LIMIT + 1).collect::<Vec<_>>();
+        if schedule.len() > SCHEDULE_LIMIT {
+            return Err(ScheduleError::Unbounded)
         }
+        Ok(schedule)
+    }
+
+    /// Returns an iterator over the delays between attempts that this strategy produces.
+    ///
+    /// The number of delays is known upfront, so the iterator implements `ExactSizeIterator`.
+    /// If jitter is enabled, the delays are randomized anew on every call.
+    ///
+    /// If a delay hook is set (see [`with_delay_hook`](#method.with_delay_hook)), it is called for
+    /// every delay that the iterator yields, just like during a retry, so any state that it keeps
+    /// advances as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the strategy has a total delay budget (see [`with_max_total_delay`](#method.with_max_total_delay)),
+    /// since the budget may stop the delays early. Use [`budgeted_delays`](#method.budgeted_delays)
+    /// for such strategies.
+    pub fn delays(&self) -> Delays {
+        assert!(self.max_total_delay.is_none(), "strategy with a total delay budget has no exact number of delays");
+        Delays(self.iter())
+    }
+
+    /// Returns an iterator over the delays between attempts that this strategy produces,
+    /// which may stop early because of a total delay budget.
+    ///
+    /// Unlike [`delays`](#method.delays), this works for every strategy, but the iterator
+    /// only reports an upper bound of the number of delays.
+    /// If jitter is enabled, the delays are randomized anew on every call.
+    ///
+    /// If a delay hook is set, it is called for every delay that the iterator yields,
+    /// just like during a retry, so any state that it keeps advances as well.
+    pub fn budgeted_delays(&self) -> BudgetedDelays {
+        BudgetedDelays(self.iter())
     }
 
     /// Run the given action, and use this strategy to retry on failure.
@@ -152,59 +586,484 @@ impl Strategy {
         Retry::new(self, action)
     }
 
+    /// Run the given action, and use this strategy to retry on failure until the given flag is set.
+    ///
+    /// See [`Retry::with_cancel_flag`](./struct.Retry.html#method.with_cancel_flag).
+    pub fn retry_until<A: Action>(&self, action: A, flag: Arc<AtomicBool>) -> RetryUntil<A> {
+        Retry::new(self, action).with_cancel_flag(flag)
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// Unlike [`retry`](#method.retry), the action is only borrowed, so that it can
+    /// be used again once the future has completed.
+    pub fn retry_ref<'a, A: Action>(&self, action: &'a mut A) -> Retry<ByRef<'a, A>> {
+        Retry::new(self, ByRef::new(action))
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// Unlike [`retry`](#method.retry), this only accepts actions that have been declared
+    /// safe to run more than once, see [`IdempotentAction`](./trait.IdempotentAction.html).
+    pub fn retry_idempotent<A: IdempotentAction>(&self, action: A) -> Retry<A> {
+        Retry::new(self, action)
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// If all attempts fail, the future resolves with
+    /// [`RetryError::Exhausted`](./enum.RetryError.html#variant.Exhausted), which holds
+    /// the errors of all attempts.
+    pub fn retry_rich<A: Action>(&self, action: A) -> RetryRich<A> {
+        RetryRich::new(self, action)
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// Once an attempt succeeds, the item is reported along with [`Timing`](./struct.Timing.html)
+    /// information, which separates the latency of the successful attempt from the total time.
+    pub fn retry_timed<A: Action>(&self, action: A) -> RetryTimed<A> {
+        Retry::new(self, action).timed()
+    }
+
+    /// Run the given function producing a stream, and use this strategy to retry until
+    /// a stream yields an item.
+    ///
+    /// Streams that fail or end before yielding an item count as failed attempts. Once a
+    /// stream yields an item, the future resolves with that item and drops the stream.
+    pub fn retry_stream_first<F, S>(&self, f: F) -> Retry<StreamFirst<F>>
+        where F: FnMut() -> S,
+              S: Stream
+    {
+        Retry::new(self, StreamFirst::new(f))
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// Before every attempt, the given probe is run, and the action only runs if the probe
+    /// resolves with `true`. Otherwise the attempt counts as failed without running the action.
+    /// This is useful to check the liveness of a connection before using it.
+    pub fn retry_with_probe<A, P, T>(&self, action: A, probe: P) -> RetryWithProbe<A, P, T>
+        where A: Action,
+              P: FnMut() -> T,
+              T: IntoFuture<Item=bool, Error=A::Error>
+    {
+        RetryWithProbe::new(self, action, probe)
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// Every attempt receives a mutable reference to `context`, which is returned along
+    /// with the result of the last attempt. This can be used to collect information
+    /// about the attempts, such as tracing data.
+    pub fn retry_with_context<F, Ctx, T>(&self, action: F, context: Ctx) -> RetryWithContext<F, Ctx, T>
+        where F: FnMut(&mut Ctx) -> T,
+              T: IntoFuture
+    {
+        RetryWithContext::new(self, action, context)
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// The given [`SleepDriver`](./trait.SleepDriver.html) is used to sleep between attempts.
+    pub fn retry_with<A: Action, D: SleepDriver>(&self, action: A, driver: D) -> Retry<A, D> {
+        Retry::new_with_driver(driver, self, action)
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// Every attempt and every sleep between attempts is reported to the given
+    /// [`Observer`](./trait.Observer.html), for example to record the latency of each attempt.
+    pub fn retry_observed<A: Action, O: Observer>(&self, action: A, observer: O) -> RetryObserved<A, O> {
+        RetryObserved::new(self, action, observer)
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// Returns a [`RetryHandle`](./struct.RetryHandle.html) along with the future,
+    /// which can be used to observe the progress of the future.
+    pub fn retry_with_handle<A: Action>(&self, action: A) -> (Retry<A>, RetryHandle) {
+        Retry::new(self, action).observed()
+    }
+
     /// Run the given action, and use this strategy to retry on failure if the error satisfies a given condition.
     pub fn retry_if<A: Action, C>(&self, action: A, condition: C) -> RetryIf<A, C>
         where C: Condition<A::Error>
     {
         RetryIf::new(self, action, condition)
     }
+
+    /// Run the given action, and use this strategy to retry on failure if the error and the time
+    /// elapsed since the first attempt satisfy a given condition.
+    pub fn retry_if_elapsed<A: Action, C>(&self, action: A, condition: C) -> RetryIf<A, Elapsed<C>>
+        where C: FnMut(&A::Error, Duration) -> bool
+    {
+        RetryIf::new(self, action, Elapsed(condition))
+    }
+
+    /// Run the given action, and use this strategy to retry on failure if the decision
+    /// for the error says so.
+    ///
+    /// The decision may grant extra retries, which are added to the remaining retries
+    /// of this strategy. Note that an attempt may have been told that it is the last one
+    /// (see [`RunContext::is_last`](./struct.RunContext.html#method.is_last)) before
+    /// its error granted extra retries.
+    pub fn retry_if_decision<A: Action, F>(&self, action: A, decision: F) -> RetryIf<A, Decision<F>>
+        where F: FnMut(&A::Error) -> RetryDecision
+    {
+        RetryIf::new(self, action, Decision(decision))
+    }
+
+    /// Run the given action, and use this strategy to retry on failure.
+    ///
+    /// If the error suggests a delay via [`RetryAfter`](./trait.RetryAfter.html), it is used
+    /// instead of the delay computed by this strategy, but still limited by the maximum delay.
+    pub fn retry_respecting_hint<A: Action>(&self, action: A) -> Retry<A>
+        where A::Error: RetryAfter
+    {
+        Retry::new(self, action).respecting_hint()
+    }
+
+    /// Run the given action, and use this strategy to retry on failure. Once the retries are
+    /// exhausted, `on_exhausted` is run with the last error to produce a fallback result.
+    pub fn retry_or_else<A: Action, G, F>(&self, action: A, on_exhausted: G) -> RetryOrElse<A, G, F>
+        where G: FnOnce(A::Error) -> F,
+              F: IntoFuture<Item=A::Item, Error=A::Error>
+    {
+        RetryOrElse::new(self, action, on_exhausted)
+    }
+
+    /// Run the given fallible synchronous function, and use this strategy to retry on failure.
+    ///
+    /// The returned future sleeps between attempts without blocking the executor.
+    pub fn retry_sync_in_place<F, T, E>(&self, f: F) -> Retry<SyncFn<F>>
+        where F: FnMut() -> Result<T, E>
+    {
+        Retry::new(self, sync_fn(f))
+    }
+
+    /// Run the given action, and use this strategy to retry on failure or panic.
+    ///
+    /// Panics of the action, or of the future it produces, are caught and retried like errors.
+    /// If the last attempt panicked, the panic is resumed once the retries are exhausted.
+    pub fn catch_unwind_retry<A: Action>(&self, action: A) -> CatchUnwindRetry<A>
+        where A::Future: UnwindSafe
+    {
+        CatchUnwindRetry::new(self, action)
+    }
+
+    /// Run the given actions, and use this strategy to retry each of them on failure.
+    ///
+    /// At most `concurrency` actions run at once. The returned stream yields the result
+    /// of each action as soon as it completes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is zero.
+    pub fn retry_buffered<I>(&self, actions: I, concurrency: usize) -> RetryBuffered<I::IntoIter>
+        where I: IntoIterator,
+              I::Item: Action
+    {
+        RetryBuffered::new(self, actions, concurrency)
+    }
+
+    /// Run the given action, and use this strategy to hedge it.
+    ///
+    /// Each time the delay elapses, another attempt is launched without cancelling
+    /// the attempts that are still in flight. The first attempt to succeed wins.
+    pub fn retry_hedged<A: Action>(&self, action: A) -> RetryHedged<A> {
+        RetryHedged::new(self, action)
+    }
+}
+
+/// Multiplies the delay by a floating point factor.
+///
+/// Saturates at `Duration::MAX` instead of overflowing, and at zero for negative factors.
+fn scale(delay: Duration, factor: f64) -> Duration {
+    let secs = delay.as_secs_f64() * factor;
+    if secs.is_nan() || secs <= 0.0 {
+        return Duration::from_secs(0)
+    }
+    Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
 }
 
+#[derive(Clone)]
 enum FactorIter {
     Exponential(ExponentialBackoff),
     Fibonacci(FibonacciBackoff),
     Fixed(FixedInterval),
+    Function(FactorFn),
+    Delays(DelaySequence),
+    Multiplier(MultiplierBackoff),
 }
 
-impl Iterator for FactorIter {
-    type Item = u32;
-
-    fn next(&mut self) -> Option<u32> {
-        match self {
+impl FactorIter {
+    /// Returns the next delay, multiplying the base delay by the next factor.
+    fn next_delay(&mut self, base: Duration) -> Option<Duration> {
+        let factor = match self {
             &mut FactorIter::Exponential(ref mut iter) => iter.next(),
             &mut FactorIter::Fibonacci(ref mut iter) => iter.next(),
             &mut FactorIter::Fixed(ref mut iter) => iter.next(),
+            &mut FactorIter::Function(ref mut iter) => iter.next(),
+            &mut FactorIter::Delays(ref mut iter) => return iter.next(),
+            &mut FactorIter::Multiplier(ref mut iter) => return iter.next().map(|factor| scale(base, factor)),
+        };
+        Some(base.saturating_mul(factor.unwrap_or(u32::MAX)))
+    }
+
+    /// Returns the bounds on the number of delays that remain, ignoring retry limits.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            FactorIter::Delays(ref iter) => iter.size_hint(),
+            _ => (usize::MAX, None)
         }
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct StrategyIter {
     factor_iter: FactorIter,
     delay: Duration,
     max_delay: Option<Duration>,
     retries: usize,
-    jitter: bool
+    max_total_delay: Option<Duration>,
+    total_delay: Duration,
+    max_consecutive_failures: Option<usize>,
+    consecutive_failures: usize,
+    jitter: bool,
+    absolute_jitter: Option<(Duration, Duration)>,
+    jitter_after_clamp: bool,
+    random_source: SharedRandomSource,
+    delay_hook: Option<DelayHook>,
+    index: usize,
+    peeked: Option<Option<Duration>>,
+    #[cfg(feature = "log")]
+    clamp_warned: bool
+}
+
+impl StrategyIter {
+    /// Limits the given delay to the maximum delay.
+    pub(crate) fn clamp(&self, delay: Duration) -> Duration {
+        match self.max_delay {
+            Some(max_delay) => ::std::cmp::min(delay, max_delay),
+            None => delay
+        }
+    }
+
+    /// Returns the number of delays that remain.
+    pub(crate) fn retries_remaining(&self) -> usize {
+        match self.peeked {
+            Some(Some(_)) => self.retries + 1,
+            _ => self.retries
+        }
+    }
+
+    /// Returns whether no further delay will follow, without computing the next delay.
+    ///
+    /// Computing the next delay would draw jitter and call the delay hook, which should only
+    /// happen once an attempt has failed. With a total delay budget, whether the budget allows
+    /// another delay is not known in advance, so this conservatively returns `false`.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        match self.peeked {
+            Some(ref delay) => delay.is_none(),
+            None => self.size_hint().1 == Some(0)
+        }
+    }
+
+    /// Clears the streak of consecutive failures, after a success.
+    pub(crate) fn reset_consecutive_failures(&mut self) {
+        self.consecutive_failures = 0;
+        if let Some(None) = self.peeked {
+            self.peeked = None;
+        }
+    }
+
+    /// Returns the number of delays that the streak of consecutive failures allows.
+    fn consecutive_failures_remaining(&self) -> Option<usize> {
+        self.max_consecutive_failures.map(|max_consecutive_failures| {
+            // The last failure of the streak gives up instead of being followed by a delay.
+            max_consecutive_failures.saturating_sub(1).saturating_sub(self.consecutive_failures)
+        })
+    }
+
+    /// Adds to the number of delays that remain.
+    pub(crate) fn extend(&mut self, retries: usize) {
+        if retries == 0 {
+            return
+        }
+        self.retries = self.retries.saturating_add(retries);
+        if let Some(None) = self.peeked {
+            self.peeked = None;
+        }
+    }
+
+    /// Returns the next delay without consuming it.
+    #[cfg(test)]
+    pub(crate) fn peek(&mut self) -> Option<&Duration> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.compute_next());
+        }
+        match self.peeked {
+            Some(ref delay) => delay.as_ref(),
+            None => None
+        }
+    }
+
+    /// Applies the relative and absolute jitter to the given delay.
+    fn apply_jitter(&self, mut delay: Duration) -> Duration {
+        if self.jitter {
+            delay = self.random_source.jitter(delay);
+        }
+        if let Some((min, max)) = self.absolute_jitter {
+            let jitter = self.random_source.jitter(max.checked_sub(min).unwrap_or_default());
+            delay = delay.saturating_add(min.saturating_add(jitter));
+        }
+        delay
+    }
+
+    /// Computes the next delay.
+    ///
+    /// Returns `None` only if the retries (or the explicitly given delays, or the
+    /// total delay budget, or the consecutive failures) are exhausted. If the delay
+    /// overflows, it saturates at the maximum delay instead.
+    ///
+    /// The delay is jittered and limited to the maximum delay, in the order chosen by
+    /// `jitter_after_clamp`, before the delay hook and the total delay budget apply.
+    fn compute_next(&mut self) -> Option<Duration> {
+        if self.retries == 0 || self.consecutive_failures_remaining() == Some(0) {
+            return None
+        }
+        let mut delay = self.factor_iter.next_delay(self.delay)?;
+        if !self.jitter_after_clamp {
+            delay = self.apply_jitter(delay);
+        }
+        let clamped = self.clamp(delay);
+        #[cfg(feature = "log")]
+        {
+            if clamped < delay && !self.clamp_warned {
+                self.clamp_warned = true;
+                warn!("retry delay of {:?} was clamped to the maximum delay of {:?}, later delays will not grow any further",
+                      delay, clamped);
+            }
+        }
+        delay = clamped;
+        if self.jitter_after_clamp {
+            delay = self.apply_jitter(delay);
+        }
+        if let Some(ref hook) = self.delay_hook {
+            delay = hook.call(self.index, delay);
+        }
+        if let Some(max_total_delay) = self.max_total_delay {
+            // Account for the delay after jitter, since that is what will be slept.
+            let total_delay = self.total_delay.saturating_add(delay);
+            if total_delay > max_total_delay {
+                self.retries = 0;
+                return None
+            }
+            self.total_delay = total_delay;
+        }
+        self.retries -= 1;
+        self.consecutive_failures += 1;
+        self.index += 1;
+        Some(delay)
+    }
 }
 
 impl Iterator for StrategyIter {
     type Item = Duration;
 
     fn next(&mut self) -> Option<Duration> {
-        if self.retries > 0 {
-            if let Some(factor) = self.factor_iter.next() {
-                if let Some(mut delay) = self.delay.checked_mul(factor) {
-                    if self.jitter {
-                        delay = jitter(delay);
-                    }
-                    if let Some(max_delay) = self.max_delay {
-                        delay = ::std::cmp::min(delay, max_delay);
-                    }
-                    self.retries -= 1;
-                    return Some(delay)
-                }
-            }
+        match self.peeked.take() {
+            Some(delay) => delay,
+            None => self.compute_next()
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let peeked = match self.peeked {
+            Some(Some(_)) => 1,
+            Some(None) => return (0, Some(0)),
+            None => 0
+        };
+        let mut remaining = match self.factor_iter.size_hint() {
+            (_, Some(factors)) => ::std::cmp::min(self.retries, factors),
+            (_, None) => self.retries
+        };
+        if let Some(failures) = self.consecutive_failures_remaining() {
+            remaining = ::std::cmp::min(remaining, failures);
+        }
+        remaining = remaining.saturating_add(peeked);
+        if self.max_total_delay.is_some() {
+            // The total delay budget may cut the iteration short at any point.
+            (peeked, Some(remaining))
+        } else {
+            (remaining, Some(remaining))
         }
-        None
+    }
+}
+
+/// Iterator over the delays between attempts of a strategy without a total delay budget.
+///
+/// The number of delays is known upfront, so this iterator implements `ExactSizeIterator`.
+///
+/// Created by [`Strategy::delays`](./struct.Strategy.html#method.delays).
+pub struct Delays(StrategyIter);
+
+impl fmt::Debug for Delays {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Delays").finish()
+    }
+}
+
+impl Iterator for Delays {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Delays {}
+
+/// Iterator over the delays between attempts of any strategy.
+///
+/// Strategies with a total delay budget (see [`with_max_total_delay`](./struct.Strategy.html#method.with_max_total_delay))
+/// may stop early, so the number of delays is not known upfront, and this iterator does
+/// not implement `ExactSizeIterator`:
+///
+/// ```rust,compile_fail
+/// # extern crate futures_backoff;
+/// # use std::time::Duration;
+/// # use futures_backoff::Strategy;
+/// # fn main() {
+/// let strategy = Strategy::fixed(Duration::from_millis(10))
+///     .with_max_total_delay(Duration::from_millis(25));
+/// strategy.budgeted_delays().len();
+/// # }
+/// ```
+///
+/// Created by [`Strategy::budgeted_delays`](./struct.Strategy.html#method.budgeted_delays).
+pub struct BudgetedDelays(StrategyIter);
+
+impl fmt::Debug for BudgetedDelays {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BudgetedDelays").finish()
+    }
+}
+
+impl Iterator for BudgetedDelays {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
 
@@ -217,6 +1076,500 @@ fn fixed_returns_delay() {
     assert_eq!(s.next(), Some(Duration::from_millis(123)));
 }
 
+#[test]
+fn peek_does_not_consume_the_next_delay() {
+    let mut s = Strategy::exponential(Duration::from_millis(10))
+      .with_max_retries(2).iter();
+
+    assert_eq!(s.peek(), Some(&Duration::from_millis(10)));
+    assert_eq!(s.next(), Some(Duration::from_millis(10)));
+    assert_eq!(s.next(), Some(Duration::from_millis(20)));
+    assert_eq!(s.peek(), None);
+    assert_eq!(s.next(), None);
+}
+
+#[test]
+fn jitter_uses_the_given_random_source() {
+    struct MaxRandomSource;
+
+    impl RandomSource for MaxRandomSource {
+        fn jitter(&mut self, max: Duration) -> Duration {
+            max
+        }
+    }
+
+    let mut s = Strategy::exponential(Duration::from_millis(10))
+      .with_jitter(true)
+      .with_random_source(MaxRandomSource).iter();
+
+    assert_eq!(s.next(), Some(Duration::from_millis(10)));
+    assert_eq!(s.next(), Some(Duration::from_millis(20)));
+    assert_eq!(s.next(), Some(Duration::from_millis(40)));
+}
+
+#[test]
+fn clamp_limits_to_max_delay() {
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_delay(Duration::from_millis(100)).iter();
+
+    assert_eq!(s.clamp(Duration::from_millis(50)), Duration::from_millis(50));
+    assert_eq!(s.clamp(Duration::from_millis(200)), Duration::from_millis(100));
+}
+
+#[test]
+fn iteration_length_equals_max_retries_for_tiny_base() {
+    let s = Strategy::exponential(Duration::new(0, 1))
+      .with_max_retries(100);
+
+    assert_eq!(s.iter().count(), 100);
+}
+
+#[test]
+fn overflowing_delay_saturates_instead_of_stopping() {
+    let mut s = Strategy::exponential(Duration::from_secs(u64::MAX / 2))
+      .with_max_delay(Duration::from_secs(60))
+      .with_max_retries(10).iter();
+
+    assert_eq!(s.next(), Some(Duration::from_secs(60)));
+    assert_eq!(s.next(), Some(Duration::from_secs(60)));
+    assert_eq!(s.next(), Some(Duration::from_secs(60)));
+    assert_eq!(s.count(), 7);
+}
+
+#[test]
+fn extreme_configuration_does_not_panic() {
+    let strategies = vec![
+        Strategy::exponential(Duration::MAX),
+        Strategy::fibonacci(Duration::MAX),
+        Strategy::fixed(Duration::MAX),
+        Strategy::factor_fn(Duration::MAX, |_| u32::MAX)
+    ];
+
+    for s in strategies {
+        let s = s
+          .with_max_delay(Duration::MAX)
+          .with_max_retries(usize::MAX)
+          .with_jitter(true)
+          .skip_first_delay();
+        for delay in s.iter().take(100) {
+            deadline(Instant::now(), delay);
+        }
+        s.to_string();
+    }
+}
+
+#[test]
+fn deadline_saturates_instead_of_overflowing() {
+    let now = Instant::now();
+
+    assert_eq!(deadline(now, Duration::from_secs(1)), now + Duration::from_secs(1));
+    assert!(deadline(now, Duration::MAX) > now);
+}
+
+#[test]
+fn exponential_bounded_plateaus_after_max_shift() {
+    let s = Strategy::exponential_bounded(Duration::from_millis(1), 3)
+      .with_max_retries(6);
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_millis(1),
+        Duration::from_millis(2),
+        Duration::from_millis(4),
+        Duration::from_millis(8),
+        Duration::from_millis(8),
+        Duration::from_millis(8)
+    ]);
+}
+
+#[test]
+fn exponential_bounded_limits_large_shifts() {
+    let mut s = Strategy::exponential_bounded(Duration::from_millis(1), 40)
+      .with_max_retries(40).iter();
+
+    assert_eq!(s.nth(35), Some(Duration::from_millis(1 << 31)));
+}
+
+#[test]
+fn delay_hook_decides_on_the_delay() {
+    let s = Strategy::exponential(Duration::from_millis(10))
+      .with_max_delay(Duration::from_millis(40))
+      .with_max_retries(5)
+      .with_delay_hook(|index, delay| {
+          if index % 2 == 0 { Duration::from_secs(0) } else { delay * 2 }
+      });
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_millis(0),
+        Duration::from_millis(40),
+        Duration::from_millis(0),
+        Duration::from_millis(80),
+        Duration::from_millis(0)
+    ]);
+}
+
+#[test]
+fn formatting_does_not_call_the_delay_hook() {
+    let calls = Arc::new(::std::sync::atomic::AtomicUsize::new(0));
+    let hook_calls = calls.clone();
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(3)
+      .with_delay_hook(move |_, delay| {
+          hook_calls.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+          delay * 2
+      });
+
+    assert_eq!(s.to_string(), "fixed base=10ms max_delay=none retries=3 jitter=off -> [10ms, 10ms, 10ms]");
+    let _ = format!("{:?}", s);
+    assert_eq!(calls.load(::std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+#[test]
+fn inspecting_the_delays_calls_the_delay_hook() {
+    let calls = Arc::new(::std::sync::atomic::AtomicUsize::new(0));
+    let hook_calls = calls.clone();
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(3)
+      .with_delay_hook(move |_, delay| {
+          hook_calls.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+          delay
+      });
+
+    s.schedule().unwrap();
+    assert_eq!(calls.load(::std::sync::atomic::Ordering::SeqCst), 3);
+    assert_eq!(s.delays().take(2).count(), 2);
+    assert_eq!(calls.load(::std::sync::atomic::Ordering::SeqCst), 5);
+}
+
+#[test]
+fn delay_hook_survives_a_panic() {
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(3)
+      .with_delay_hook({
+          let mut panicked = false;
+          move |_, delay| {
+              if !panicked {
+                  panicked = true;
+                  panic!("first call");
+              }
+              delay
+          }
+      });
+
+    let mut delays = s.iter();
+    let panicked = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| delays.next()));
+    assert!(panicked.is_err());
+    assert_eq!(s.schedule().unwrap(), vec![Duration::from_millis(10); 3]);
+}
+
+#[test]
+fn scaled_multiplies_all_delays() {
+    let strategy = || Strategy::exponential(Duration::from_millis(15))
+      .with_max_delay(Duration::from_millis(100))
+      .with_max_retries(5);
+    let doubled = strategy().schedule().unwrap().into_iter().map(|delay| delay * 2).collect::<Vec<_>>();
+
+    assert_eq!(strategy().scaled(2.0).schedule().unwrap(), doubled);
+}
+
+#[test]
+fn scaled_scales_explicit_delays() {
+    let s = Strategy::from_delays(&[Duration::from_millis(10), Duration::from_millis(30)])
+      .scaled(0.5);
+
+    assert_eq!(s.schedule().unwrap(), vec![Duration::from_millis(5), Duration::from_millis(15)]);
+}
+
+#[test]
+fn exponential_full_matches_the_backoff_crate() {
+    // The documented intervals of the `backoff` crate for an initial interval of 500ms,
+    // a multiplier of 1.5 and a maximum interval of 10s, without randomization.
+    let s = Strategy::exponential_full(Duration::from_millis(500), 1.5, Duration::from_secs(10), 10);
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_millis(500),
+        Duration::from_millis(750),
+        Duration::from_micros(1_125_000),
+        Duration::from_micros(1_687_500),
+        Duration::from_nanos(2_531_250_000),
+        Duration::from_nanos(3_796_875_000),
+        Duration::from_nanos(5_695_312_500),
+        Duration::from_nanos(8_542_968_750),
+        Duration::from_secs(10),
+        Duration::from_secs(10)
+    ]);
+}
+
+#[test]
+fn exponential_full_saturates_instead_of_overflowing() {
+    let s = Strategy::exponential_full(Duration::from_secs(1), 1e300, Duration::MAX, 5);
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_secs(1),
+        Duration::MAX,
+        Duration::MAX,
+        Duration::MAX,
+        Duration::MAX
+    ]);
+}
+
+#[test]
+fn max_consecutive_failures_limits_the_streak() {
+    let mut s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(100)
+      .with_max_consecutive_failures(3).iter();
+
+    assert_eq!(s.size_hint(), (2, Some(2)));
+    assert_eq!(s.next(), Some(Duration::from_millis(10)));
+    s.reset_consecutive_failures();
+    assert_eq!(s.next(), Some(Duration::from_millis(10)));
+    assert_eq!(s.next(), Some(Duration::from_millis(10)));
+    assert_eq!(s.peek(), None);
+    s.reset_consecutive_failures();
+    assert_eq!(s.next(), Some(Duration::from_millis(10)));
+}
+
+#[test]
+fn delays_have_exact_size() {
+    let s = Strategy::exponential(Duration::from_millis(10))
+      .with_max_retries(4);
+    let mut delays = s.delays();
+
+    assert_eq!(delays.len(), 4);
+    assert_eq!(delays.size_hint(), (4, Some(4)));
+    delays.next();
+    assert_eq!(delays.size_hint(), (3, Some(3)));
+    assert_eq!(delays.count(), 3);
+}
+
+#[test]
+#[should_panic(expected = "total delay budget")]
+fn delays_refuses_a_total_delay_budget() {
+    Strategy::fixed(Duration::from_millis(10))
+      .with_max_total_delay(Duration::from_millis(25))
+      .delays();
+}
+
+#[test]
+fn budgeted_delays_size_is_an_upper_bound() {
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(usize::MAX)
+      .with_max_total_delay(Duration::from_millis(30));
+    let delays = s.budgeted_delays();
+
+    assert_eq!(delays.size_hint(), (0, Some(usize::MAX)));
+    assert_eq!(delays.collect::<Vec<_>>(), vec![Duration::from_millis(10); 3]);
+}
+
+#[test]
+fn schedule_refuses_unbounded_strategies() {
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(usize::MAX);
+
+    assert_eq!(s.schedule(), Err(ScheduleError::Unbounded));
+    assert_eq!(s.delays().len(), usize::MAX);
+    assert_eq!(s.delays().take(3).count(), 3);
+}
+
+#[test]
+fn schedule_refuses_strategies_exceeding_the_limit_lazily() {
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(usize::MAX)
+      .with_max_total_delay(Duration::from_secs(3600));
+
+    assert_eq!(s.schedule(), Err(ScheduleError::Unbounded));
+}
+
+#[test]
+fn schedule_collects_bounded_strategies() {
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(usize::MAX)
+      .with_max_total_delay(Duration::from_millis(30));
+
+    assert_eq!(s.schedule(), Ok(vec![Duration::from_millis(10); 3]));
+    assert_eq!(Strategy::default().schedule().map(|delays| delays.len()), Ok(5));
+}
+
+#[test]
+fn iterates_over_a_borrowed_strategy() {
+    let s = Strategy::fibonacci(Duration::from_millis(10))
+      .with_max_retries(5);
+    let mut delays = Vec::new();
+    for delay in &s {
+        delays.push(delay);
+    }
+
+    assert_eq!(delays, s.schedule().unwrap());
+}
+
+#[test]
+fn delays_size_is_limited_by_explicit_delays() {
+    let s = Strategy::from_delays(&[Duration::from_millis(10), Duration::from_millis(20)])
+      .with_max_retries(5);
+
+    assert_eq!(s.delays().len(), 2);
+    assert_eq!(s.delays().count(), 2);
+}
+
+#[test]
+fn delays_size_is_an_upper_bound_with_a_total_delay_budget() {
+    let s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(5)
+      .with_max_total_delay(Duration::from_millis(25));
+    let mut delays = s.iter();
+
+    assert_eq!(delays.size_hint(), (0, Some(5)));
+    delays.peek();
+    assert_eq!(delays.size_hint(), (1, Some(5)));
+    assert_eq!(delays.count(), 2);
+}
+
+#[test]
+fn retries_remaining_accounts_for_peeked_delay() {
+    let mut s = Strategy::fixed(Duration::from_millis(10))
+      .with_max_retries(2).iter();
+
+    assert_eq!(s.retries_remaining(), 2);
+    s.peek();
+    assert_eq!(s.retries_remaining(), 2);
+    s.next();
+    assert_eq!(s.retries_remaining(), 1);
+    s.next();
+    s.peek();
+    assert_eq!(s.retries_remaining(), 0);
+}
+
+#[cfg(test)]
+struct SeededRandomSource(::rand::XorShiftRng);
+
+#[cfg(test)]
+impl SeededRandomSource {
+    fn new() -> SeededRandomSource {
+        use rand::SeedableRng;
+        SeededRandomSource(::rand::XorShiftRng::from_seed([1, 2, 3, 4]))
+    }
+}
+
+#[cfg(test)]
+impl RandomSource for SeededRandomSource {
+    fn jitter(&mut self, max: Duration) -> Duration {
+        use rand::Rng;
+        let nanos = self.0.gen_range(0, max.as_nanos() as u64 + 1);
+        Duration::from_nanos(nanos)
+    }
+}
+
+#[test]
+fn absolute_jitter_stays_within_bounds() {
+    let s = Strategy::fixed(Duration::from_millis(100))
+      .with_absolute_jitter(Duration::from_millis(10), Duration::from_millis(50))
+      .with_random_source(SeededRandomSource::new())
+      .with_max_retries(100);
+
+    for delay in s.iter() {
+        assert!(delay >= Duration::from_millis(110));
+        assert!(delay <= Duration::from_millis(150));
+    }
+}
+
+#[test]
+fn absolute_jitter_does_not_exceed_max_delay() {
+    let s = Strategy::fixed(Duration::from_millis(100))
+      .with_absolute_jitter(Duration::from_millis(10), Duration::from_millis(50))
+      .with_random_source(SeededRandomSource::new())
+      .with_max_delay(Duration::from_millis(120))
+      .with_max_retries(100);
+
+    let schedule = s.schedule().unwrap();
+    assert!(schedule.iter().all(|delay| *delay >= Duration::from_millis(110)));
+    assert!(schedule.iter().all(|delay| *delay <= Duration::from_millis(120)));
+    assert!(schedule.iter().any(|delay| *delay == Duration::from_millis(120)));
+}
+
+#[test]
+fn jitter_before_clamp_piles_up_at_max_delay() {
+    let delays = Strategy::fixed(Duration::from_secs(1))
+      .with_jitter(true)
+      .with_random_source(SeededRandomSource::new())
+      .with_max_delay(Duration::from_millis(100))
+      .with_max_retries(1000)
+      .schedule().unwrap();
+
+    let at_max = delays.iter().filter(|delay| **delay == Duration::from_millis(100)).count();
+    assert!(delays.iter().all(|delay| *delay <= Duration::from_millis(100)));
+    assert!(at_max > 850, "only {} of the delays are at the maximum", at_max);
+}
+
+#[test]
+fn jitter_after_clamp_spreads_below_max_delay() {
+    let delays = Strategy::fixed(Duration::from_secs(1))
+      .with_jitter(true)
+      .with_jitter_after_clamp(true)
+      .with_random_source(SeededRandomSource::new())
+      .with_max_delay(Duration::from_millis(100))
+      .with_max_retries(1000)
+      .schedule().unwrap();
+
+    let at_max = delays.iter().filter(|delay| **delay == Duration::from_millis(100)).count();
+    let below_half = delays.iter().filter(|delay| **delay < Duration::from_millis(50)).count();
+    assert!(delays.iter().all(|delay| *delay <= Duration::from_millis(100)));
+    assert_eq!(at_max, 0);
+    assert!(below_half > 400 && below_half < 600, "{} of the delays are below half the maximum", below_half);
+}
+
+#[test]
+fn absolute_jitter_after_clamp_exceeds_max_delay() {
+    let s = Strategy::fixed(Duration::from_millis(100))
+      .with_absolute_jitter(Duration::from_millis(10), Duration::from_millis(50))
+      .with_jitter_after_clamp(true)
+      .with_random_source(SeededRandomSource::new())
+      .with_max_delay(Duration::from_millis(100))
+      .with_max_retries(100);
+
+    for delay in s.iter() {
+        assert!(delay >= Duration::from_millis(110));
+        assert!(delay <= Duration::from_millis(150));
+    }
+}
+
+#[test]
+fn max_total_delay_stops_before_budget_is_exceeded() {
+    let s = Strategy::exponential(Duration::from_millis(10))
+      .with_max_total_delay(Duration::from_millis(100))
+      .with_max_retries(10);
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_millis(10),
+        Duration::from_millis(20),
+        Duration::from_millis(40)
+    ]);
+}
+
+#[test]
+fn max_total_delay_accounts_for_jitter() {
+    let budget = Duration::from_millis(300);
+    let jittered = Strategy::fixed(Duration::from_millis(100))
+      .with_jitter(true)
+      .with_random_source(SeededRandomSource::new())
+      .with_max_retries(100)
+      .schedule().unwrap();
+    let budgeted = Strategy::fixed(Duration::from_millis(100))
+      .with_jitter(true)
+      .with_random_source(SeededRandomSource::new())
+      .with_max_total_delay(budget)
+      .with_max_retries(100)
+      .schedule().unwrap();
+
+    let mut total = Duration::from_secs(0);
+    let expected = jittered.iter().take_while(|delay| {
+        total += **delay;
+        total <= budget
+    }).count();
+
+    assert!(budgeted.len() > 3);
+    assert_eq!(budgeted.len(), expected);
+    assert_eq!(&budgeted[..], &jittered[..expected]);
+}
+
 #[test]
 fn fibonacci_returns_the_fibonacci_series_starting_at_10() {
     let mut s = Strategy::fibonacci(Duration::from_millis(10)).iter();
@@ -249,6 +1602,42 @@ fn fibonacci_returns_max_when_max_less_than_base() {
     assert_eq!(s.next(), Some(Duration::from_millis(10)));
 }
 
+#[test]
+fn factor_fn_returns_multiples_of_the_custom_factor() {
+    let s = Strategy::factor_fn(Duration::from_millis(10), |n| n.clamp(1, 4))
+      .with_max_retries(6);
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_millis(10),
+        Duration::from_millis(10),
+        Duration::from_millis(20),
+        Duration::from_millis(30),
+        Duration::from_millis(40),
+        Duration::from_millis(40)
+    ]);
+}
+
+#[test]
+fn from_delays_stops_at_the_end() {
+    let s = Strategy::from_delays(&[Duration::from_millis(10), Duration::from_millis(500)])
+      .with_max_retries(5);
+
+    assert_eq!(s.schedule().unwrap(), vec![Duration::from_millis(10), Duration::from_millis(500)]);
+}
+
+#[test]
+fn cycle_delays_starts_over_at_the_end() {
+    let s = Strategy::cycle_delays(&[Duration::from_millis(10), Duration::from_millis(500)]);
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_millis(10),
+        Duration::from_millis(500),
+        Duration::from_millis(10),
+        Duration::from_millis(500),
+        Duration::from_millis(10)
+    ]);
+}
+
 #[test]
 fn exponential_returns_multiples_of_10ms() {
     let mut s = Strategy::exponential(Duration::from_millis(10)).iter();
@@ -267,6 +1656,65 @@ fn exponential_returns_multiples_of_100ms() {
     assert_eq!(s.next(), Some(Duration::from_millis(400)));
 }
 
+#[test]
+fn from_duration_matches_exponential() {
+    let from: Strategy = Duration::from_millis(10).into();
+    let exponential = Strategy::exponential(Duration::from_millis(10));
+
+    assert_eq!(from.iter().collect::<Vec<_>>(), exponential.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn exponential_skips_first_delay() {
+    let s = Strategy::exponential(Duration::from_millis(10))
+      .with_max_retries(3);
+    let skipped = Strategy::exponential(Duration::from_millis(10))
+      .with_max_retries(3)
+      .skip_first_delay();
+
+    assert_eq!(s.iter().collect::<Vec<_>>(), vec![
+        Duration::from_millis(10),
+        Duration::from_millis(20),
+        Duration::from_millis(40)
+    ]);
+    assert_eq!(skipped.iter().collect::<Vec<_>>(), vec![
+        Duration::from_millis(20),
+        Duration::from_millis(40),
+        Duration::from_millis(80)
+    ]);
+}
+
+#[test]
+fn schedule_collects_all_delays() {
+    let s = Strategy::fibonacci(Duration::from_millis(10))
+      .with_max_retries(4);
+
+    assert_eq!(s.schedule().unwrap(), vec![
+        Duration::from_millis(10),
+        Duration::from_millis(10),
+        Duration::from_millis(20),
+        Duration::from_millis(30)
+    ]);
+}
+
+#[test]
+fn display_renders_configuration_and_schedule() {
+    let s = Strategy::exponential(Duration::from_millis(10))
+      .with_max_delay(Duration::from_millis(30))
+      .with_max_retries(3);
+
+    assert_eq!(s.to_string(), "exponential base=10ms max_delay=30ms retries=3 jitter=off -> [10ms, 20ms, 30ms]");
+    assert!(format!("{:?}", s).contains("schedule: [10ms, 20ms, 30ms]"));
+}
+
+#[test]
+fn display_truncates_long_schedules() {
+    let s = Strategy::fixed(Duration::from_secs(1))
+      .with_max_retries(100);
+
+    assert!(s.to_string().ends_with("-> [1s, 1s, 1s, 1s, 1s, 1s, 1s, 1s, 1s, 1s, ...]"));
+}
+
 #[test]
 fn exponential_stops_increasing_at_max_delay() {
     let mut s = Strategy::exponential(Duration::from_millis(20))
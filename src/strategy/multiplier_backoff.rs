@@ -0,0 +1,26 @@
+use std::iter::Iterator;
+
+#[derive(Debug, Clone)]
+pub struct MultiplierBackoff {
+    curr: f64,
+    multiplier: f64
+}
+
+impl MultiplierBackoff {
+    pub fn new(multiplier: f64) -> MultiplierBackoff {
+        MultiplierBackoff {
+            curr: 1.0,
+            multiplier
+        }
+    }
+}
+
+impl Iterator for MultiplierBackoff {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        let factor = self.curr;
+        self.curr *= self.multiplier;
+        Some(factor)
+    }
+}
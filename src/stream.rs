@@ -0,0 +1,156 @@
+use std::io::Error;
+use std::fmt;
+use std::time::Duration;
+
+use futures::{Async, Future, Poll, Stream};
+use futures_timer::TimerHandle;
+
+use super::strategy::{Strategy, StrategyIter};
+use super::driver::{SleepDriver, TimerDriver};
+
+/// Stream that sleeps for each delay of a retry strategy, and then yields the delay.
+///
+/// Created by [`backoff_stream`](./fn.backoff_stream.html).
+pub struct BackoffStream<D = TimerDriver> where D: SleepDriver {
+    strategy_iter: StrategyIter,
+    driver: D,
+    sleeping: Option<(D::Sleep, Duration)>
+}
+
+impl BackoffStream {
+    /// Creates a new backoff stream.
+    pub fn new(strategy: &Strategy) -> BackoffStream {
+        BackoffStream::new_with_handle(TimerHandle::default(), strategy)
+    }
+
+    /// Creates a new backoff stream, using the provided `handle` to schedule timeouts.
+    pub fn new_with_handle(handle: TimerHandle, strategy: &Strategy) -> BackoffStream {
+        BackoffStream::new_with_driver(TimerDriver::new(handle), strategy)
+    }
+}
+
+impl<D: SleepDriver> BackoffStream<D> {
+    /// Creates a new backoff stream, using the provided `driver` to sleep between delays.
+    pub fn new_with_driver(driver: D, strategy: &Strategy) -> BackoffStream<D> {
+        BackoffStream {
+            strategy_iter: strategy.iter(),
+            driver,
+            sleeping: None
+        }
+    }
+
+    /// Records a success, clearing the streak of consecutive failures.
+    ///
+    /// See [`Strategy::with_max_consecutive_failures`](./struct.Strategy.html#method.with_max_consecutive_failures).
+    pub fn reset(&mut self) {
+        self.strategy_iter.reset_consecutive_failures();
+    }
+}
+
+impl<D: SleepDriver> fmt::Debug for BackoffStream<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BackoffStream").finish()
+    }
+}
+
+impl<D: SleepDriver> Stream for BackoffStream<D> {
+    type Item = Duration;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let (mut delay, duration) = match self.sleeping.take() {
+            Some(sleeping) => sleeping,
+            None => match self.strategy_iter.next() {
+                None => return Ok(Async::Ready(None)),
+                Some(duration) => (self.driver.sleep(duration), duration)
+            }
+        };
+        match delay.poll()? {
+            Async::NotReady => {
+                self.sleeping = Some((delay, duration));
+                Ok(Async::NotReady)
+            },
+            Async::Ready(_) => Ok(Async::Ready(Some(duration)))
+        }
+    }
+}
+
+/// Returns a stream of the delays of the given strategy.
+///
+/// Each time the stream is polled, it first sleeps for the next delay and then yields it.
+/// This is useful to use the scheduling of a strategy in a custom loop.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::time::Duration;
+/// # use futures::{Future, Stream};
+/// # use futures_backoff::{backoff_stream, Strategy};
+/// #
+/// # fn main() {
+/// let strategy = Strategy::fixed(Duration::from_millis(10))
+///     .with_max_retries(2);
+///
+/// let delays = backoff_stream(&strategy).collect().wait().unwrap();
+/// #
+/// # assert_eq!(delays, vec![Duration::from_millis(10), Duration::from_millis(10)]);
+/// # }
+/// ```
+pub fn backoff_stream(strategy: &Strategy) -> BackoffStream {
+    BackoffStream::new(strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use futures::{Future, Stream};
+    use super::{backoff_stream, BackoffStream, Strategy};
+
+    fn next_delay(stream: &mut BackoffStream) -> Option<Duration> {
+        match stream.into_future().wait() {
+            Ok((delay, _)) => delay,
+            Err(_) => panic!("timer failed")
+        }
+    }
+
+    #[test]
+    fn yields_delays_after_sleeping() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(3);
+        let started = Instant::now();
+        let delays = backoff_stream(&s).collect().wait();
+
+        assert_eq!(delays.unwrap(), vec![
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+            Duration::from_millis(10)
+        ]);
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn gives_up_after_consecutive_failures() {
+        let s = Strategy::fixed(Duration::from_millis(1))
+            .with_max_retries(100)
+            .with_max_consecutive_failures(3);
+        let mut stream = backoff_stream(&s);
+        let outcomes = vec![false, false, true, false, true, false, false, false, false];
+        let mut failures = 0;
+
+        for (i, &succeeded) in outcomes.iter().enumerate() {
+            if succeeded {
+                stream.reset();
+                continue;
+            }
+            failures += 1;
+            if next_delay(&mut stream).is_none() {
+                assert_eq!(i, 7);
+                assert_eq!(failures, 6);
+                return;
+            }
+        }
+        panic!("stream did not give up");
+    }
+}
@@ -0,0 +1,53 @@
+//! Helpers for testing retry behavior without waiting for real delays.
+//!
+//! Available with the `testing` feature.
+
+use std::io::Error;
+use std::time::Duration;
+
+use futures::future::{self, FutureResult};
+
+use super::SleepDriver;
+
+/// Sleep driver whose sleeps resolve immediately.
+///
+/// Created by [`instant_timer`](./fn.instant_timer.html).
+#[derive(Debug, Clone, Default)]
+pub struct InstantTimer {
+    _private: ()
+}
+
+impl SleepDriver for InstantTimer {
+    type Sleep = FutureResult<(), Error>;
+
+    fn sleep(&self, _duration: Duration) -> Self::Sleep {
+        future::ok(())
+    }
+}
+
+/// Returns a sleep driver that skips all delays between attempts.
+///
+/// Pass it to [`Strategy::retry_with`](../struct.Strategy.html#method.retry_with)
+/// to exercise the retry logic of a strategy without waiting for its delays.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_backoff;
+/// # use std::time::Duration;
+/// # use futures::{Future, future};
+/// # use futures_backoff::Strategy;
+/// # use futures_backoff::testing::instant_timer;
+/// #
+/// # fn main() {
+/// let strategy = Strategy::fixed(Duration::from_secs(3600));
+///
+/// let future = strategy.retry_with(|| future::err::<(), u32>(42), instant_timer());
+/// #
+/// # assert_eq!(future.wait(), Err(42));
+/// # }
+/// ```
+pub fn instant_timer() -> InstantTimer {
+    InstantTimer::default()
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use futures::{Async, Future, Poll};
+use futures_timer::TimerHandle;
+use tower_retry::Policy;
+
+use super::strategy::{Strategy, StrategyIter};
+use super::driver::{SleepDriver, TimerDriver};
+
+/// Retry policy for [`tower-retry`](https://crates.io/crates/tower-retry), driven by a retry strategy.
+///
+/// Errors that satisfy the given classifier are retried after the next delay of the strategy,
+/// until the strategy is exhausted. Successful responses are never retried.
+///
+/// Available with the `tower` feature.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate futures_backoff;
+/// # use std::io::{Error, ErrorKind};
+/// # use futures_backoff::{Strategy, TowerPolicy};
+/// #
+/// # fn main() {
+/// let policy = TowerPolicy::new(&Strategy::default(), |err: &Error| err.kind() == ErrorKind::TimedOut);
+///
+/// // use the policy to retry a service via `tower_retry::Retry::new(policy, service)`...
+/// # let _ = policy;
+/// # }
+/// ```
+pub struct TowerPolicy<F, D = TimerDriver> where D: SleepDriver + Clone {
+    delays: StrategyIter,
+    classify: F,
+    driver: D
+}
+
+impl<F> TowerPolicy<F> {
+    /// Creates a new retry policy, retrying the errors that satisfy `classify`.
+    pub fn new(strategy: &Strategy, classify: F) -> TowerPolicy<F> {
+        TowerPolicy::new_with_handle(TimerHandle::default(), strategy, classify)
+    }
+
+    /// Creates a new retry policy, using the provided `handle` to schedule timeouts.
+    pub fn new_with_handle(handle: TimerHandle, strategy: &Strategy, classify: F) -> TowerPolicy<F> {
+        TowerPolicy::new_with_driver(TimerDriver::new(handle), strategy, classify)
+    }
+}
+
+impl<F, D: SleepDriver + Clone> TowerPolicy<F, D> {
+    /// Creates a new retry policy, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(driver: D, strategy: &Strategy, classify: F) -> TowerPolicy<F, D> {
+        TowerPolicy {
+            delays: strategy.iter(),
+            classify,
+            driver
+        }
+    }
+}
+
+impl<F: Clone, D: SleepDriver + Clone> Clone for TowerPolicy<F, D> {
+    fn clone(&self) -> TowerPolicy<F, D> {
+        TowerPolicy {
+            delays: self.delays.clone(),
+            classify: self.classify.clone(),
+            driver: self.driver.clone()
+        }
+    }
+}
+
+impl<F, D: SleepDriver + Clone> fmt::Debug for TowerPolicy<F, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TowerPolicy").finish()
+    }
+}
+
+impl<Req, Res, E, F, D> Policy<Req, Res, E> for TowerPolicy<F, D>
+    where Req: Clone,
+          F: Fn(&E) -> bool + Clone,
+          D: SleepDriver + Clone
+{
+    type Future = TowerPolicyFuture<F, D>;
+
+    fn retry(&self, _req: &Req, result: Result<&Res, &E>) -> Option<Self::Future> {
+        match result {
+            Err(err) if (self.classify)(err) => {},
+            _ => return None
+        }
+        let mut policy = self.clone();
+        let duration = policy.delays.next()?;
+        Some(TowerPolicyFuture {
+            delay: self.driver.sleep(duration),
+            policy: Some(policy)
+        })
+    }
+
+    fn clone_request(&self, req: &Req) -> Option<Req> {
+        Some(req.clone())
+    }
+}
+
+/// Future that sleeps for the next delay of a [`TowerPolicy`](./struct.TowerPolicy.html),
+/// and then resolves with the policy for the following attempt.
+pub struct TowerPolicyFuture<F, D = TimerDriver> where D: SleepDriver + Clone {
+    delay: D::Sleep,
+    policy: Option<TowerPolicy<F, D>>
+}
+
+impl<F, D: SleepDriver + Clone> fmt::Debug for TowerPolicyFuture<F, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TowerPolicyFuture").finish()
+    }
+}
+
+impl<F, D: SleepDriver + Clone> Future for TowerPolicyFuture<F, D> {
+    type Item = TowerPolicy<F, D>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.delay.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(())) => Ok(Async::Ready(self.policy.take().expect("poll called after completion"))),
+            Err(_) => Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use futures::{Async, Future, Poll};
+    use futures::future::{self, FutureResult};
+    use tower_retry::Retry;
+    use tower_service::Service;
+    use super::{Strategy, TowerPolicy};
+
+    #[derive(Clone)]
+    struct Flaky {
+        calls: Arc<AtomicUsize>
+    }
+
+    impl Service<u32> for Flaky {
+        type Response = u32;
+        type Error = &'static str;
+        type Future = FutureResult<u32, &'static str>;
+
+        fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+            Ok(Async::Ready(()))
+        }
+
+        fn call(&mut self, req: u32) -> Self::Future {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                future::err("unavailable")
+            } else {
+                future::ok(req)
+            }
+        }
+    }
+
+    #[test]
+    fn retries_the_service_according_to_the_strategy() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut service = Retry::new(
+            TowerPolicy::new(&s, |err: &&str| *err == "unavailable"),
+            Flaky { calls: calls.clone() }
+        );
+
+        assert_eq!(service.call(7).wait(), Ok(7));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn gives_up_once_the_strategy_is_exhausted() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut service = Retry::new(
+            TowerPolicy::new(&s, |_: &&str| true),
+            Flaky { calls: calls.clone() }
+        );
+
+        assert_eq!(service.call(7).wait(), Err("unavailable"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn does_not_retry_unclassified_errors() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut service = Retry::new(
+            TowerPolicy::new(&s, |_: &&str| false),
+            Flaky { calls: calls.clone() }
+        );
+
+        assert_eq!(service.call(7).wait(), Err("unavailable"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
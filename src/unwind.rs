@@ -0,0 +1,196 @@
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe, UnwindSafe};
+
+use futures::{Async, Future, Poll};
+use futures::future::CatchUnwind;
+use futures_timer::TimerHandle;
+
+use super::action::{Action, RunContext};
+use super::driver::{SleepDriver, TimerDriver};
+use super::future::Retry;
+use super::strategy::Strategy;
+
+enum Failure<E> {
+    Error(E),
+    Panic(Box<dyn Any + Send>)
+}
+
+enum CatchUnwindFuture<F> where F: Future + UnwindSafe {
+    Running(CatchUnwind<F>),
+    Panicked(Option<Box<dyn Any + Send>>)
+}
+
+impl<F: Future + UnwindSafe> Future for CatchUnwindFuture<F> {
+    type Item = F::Item;
+    type Error = Failure<F::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match *self {
+            CatchUnwindFuture::Running(ref mut future) => match future.poll() {
+                Ok(Async::NotReady) => Ok(Async::NotReady),
+                Ok(Async::Ready(Ok(item))) => Ok(Async::Ready(item)),
+                Ok(Async::Ready(Err(err))) => Err(Failure::Error(err)),
+                Err(payload) => Err(Failure::Panic(payload))
+            },
+            CatchUnwindFuture::Panicked(ref mut payload) =>
+                Err(Failure::Panic(payload.take().expect("cannot poll CatchUnwindFuture twice")))
+        }
+    }
+}
+
+struct CatchUnwindAction<A>(A);
+
+fn catch_unwind_run<F, R>(run: R) -> CatchUnwindFuture<F>
+    where F: Future + UnwindSafe,
+          R: FnOnce() -> F
+{
+    match panic::catch_unwind(AssertUnwindSafe(run)) {
+        Ok(future) => CatchUnwindFuture::Running(future.catch_unwind()),
+        Err(payload) => CatchUnwindFuture::Panicked(Some(payload))
+    }
+}
+
+impl<A: Action> Action for CatchUnwindAction<A> where A::Future: UnwindSafe {
+    type Item = A::Item;
+    type Error = Failure<A::Error>;
+    type Future = CatchUnwindFuture<A::Future>;
+
+    fn run(&mut self) -> Self::Future {
+        let action = &mut self.0;
+        catch_unwind_run(|| action.run())
+    }
+
+    fn run_with_context(&mut self, context: RunContext) -> Self::Future {
+        let action = &mut self.0;
+        catch_unwind_run(|| action.run_with_context(context))
+    }
+}
+
+/// Future that drives multiple attempts at an action via a retry strategy, treating panics
+/// of the action as retryable failures.
+///
+/// If the last attempt panicked, the panic is resumed once the retries are exhausted.
+pub struct CatchUnwindRetry<A, D = TimerDriver> where A: Action, A::Future: UnwindSafe, D: SleepDriver {
+    retry: Retry<CatchUnwindAction<A>, D>
+}
+
+impl<A: Action> CatchUnwindRetry<A> where A::Future: UnwindSafe {
+    /// Creates a new retry future.
+    pub fn new(strategy: &Strategy, action: A) -> CatchUnwindRetry<A> {
+        CatchUnwindRetry::new_with_handle(TimerHandle::default(), strategy, action)
+    }
+
+    /// Creates a new retry future, using the provided `handle` to schedule timeouts.
+    pub fn new_with_handle(handle: TimerHandle, strategy: &Strategy, action: A) -> CatchUnwindRetry<A> {
+        CatchUnwindRetry::new_with_driver(TimerDriver::new(handle), strategy, action)
+    }
+}
+
+impl<A: Action, D: SleepDriver> CatchUnwindRetry<A, D> where A::Future: UnwindSafe {
+    /// Creates a new retry future, using the provided `driver` to sleep between attempts.
+    pub fn new_with_driver(driver: D, strategy: &Strategy, action: A) -> CatchUnwindRetry<A, D> {
+        CatchUnwindRetry {
+            retry: Retry::new_with_driver(driver, strategy, CatchUnwindAction(action))
+        }
+    }
+}
+
+impl<A: Action, D: SleepDriver> fmt::Debug for CatchUnwindRetry<A, D> where A::Future: UnwindSafe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CatchUnwindRetry").finish()
+    }
+}
+
+impl<A: Action, D: SleepDriver> Future for CatchUnwindRetry<A, D> where A::Future: UnwindSafe {
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.retry.poll() {
+            Ok(async) => Ok(async),
+            Err(Failure::Error(err)) => Err(err),
+            Err(Failure::Panic(payload)) => panic::resume_unwind(payload)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use futures::{future, Future};
+    use super::{RunContext, Strategy};
+    use action::WithRunContext;
+
+    #[test]
+    fn retries_when_the_action_panics() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.catch_unwind_retry(|| {
+                num_calls += 1;
+                if num_calls < 3 {
+                    panic!("boom");
+                }
+                Ok::<u64, u64>(num_calls)
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Ok(3));
+        assert_eq!(num_calls, 3);
+    }
+
+    #[test]
+    fn retries_when_the_future_panics() {
+        let s = Strategy::fixed(Duration::from_millis(10));
+        let mut num_calls = 0;
+        let res = {
+            let fut = s.catch_unwind_retry(|| {
+                num_calls += 1;
+                let fail = num_calls < 3;
+                future::lazy(move || {
+                    if fail {
+                        panic!("boom");
+                    }
+                    Ok::<u64, u64>(42)
+                })
+            });
+            fut.wait()
+        };
+
+        assert_eq!(res, Ok(42));
+        assert_eq!(num_calls, 3);
+    }
+
+    #[test]
+    fn passes_the_run_context_to_the_action() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(2);
+        let mut attempts = Vec::new();
+        let res = {
+            let fut = s.catch_unwind_retry(WithRunContext(|context: RunContext| {
+                attempts.push((context.attempt(), context.is_last()));
+                if context.attempt() == 0 {
+                    panic!("boom");
+                }
+                Err::<u64, u64>(42)
+            }));
+            fut.wait()
+        };
+
+        assert_eq!(res, Err(42));
+        assert_eq!(attempts, vec![(0, false), (1, false), (2, true)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "boom")]
+    fn resumes_the_last_panic_once_retries_are_exhausted() {
+        let s = Strategy::fixed(Duration::from_millis(10))
+            .with_max_retries(1);
+        let fut = s.catch_unwind_retry(|| -> Result<u64, u64> {
+            panic!("boom")
+        });
+        let _ = fut.wait();
+    }
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "log")]
+
+extern crate futures_backoff;
+extern crate log;
+
+use std::sync::Once;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use futures_backoff::Strategy;
+use log::{Log, Metadata, Record};
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+static INIT: Once = Once::new();
+
+struct Capture;
+
+impl Log for Capture {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        // Only count warnings of this test, since other tests may log concurrently.
+        if record.level() == log::Level::Warn && record.args().to_string().contains("7.777s") {
+            WARNINGS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture;
+
+fn capture_warnings() {
+    INIT.call_once(|| {
+        // Another logger may already be installed, in which case warnings are not captured.
+        if log::set_logger(&CAPTURE).is_ok() {
+            log::set_max_level(log::LevelFilter::Warn);
+        }
+    });
+}
+
+#[test]
+fn warns_once_per_sequence_when_delays_are_clamped() {
+    capture_warnings();
+
+    let s = Strategy::exponential(Duration::from_secs(1))
+        .with_max_delay(Duration::from_millis(7777))
+        .with_max_retries(20);
+    s.to_string();
+    assert_eq!(WARNINGS.load(Ordering::SeqCst), 0);
+    assert_eq!(s.delays().count(), 20);
+    assert_eq!(WARNINGS.load(Ordering::SeqCst), 1);
+    assert_eq!(s.delays().count(), 20);
+    assert_eq!(WARNINGS.load(Ordering::SeqCst), 2);
+}
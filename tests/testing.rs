@@ -0,0 +1,28 @@
+#![cfg(feature = "testing")]
+
+extern crate futures;
+extern crate futures_backoff;
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use futures::Future;
+use futures_backoff::Strategy;
+use futures_backoff::testing::instant_timer;
+
+#[test]
+fn instant_timer_skips_the_delays() {
+    let strategy = Strategy::exponential(Duration::from_secs(1))
+        .with_max_retries(5);
+    let attempts = Cell::new(0);
+    let started = Instant::now();
+
+    let res = strategy.retry_with(|| {
+        attempts.set(attempts.get() + 1);
+        Err::<(), u32>(42)
+    }, instant_timer()).wait();
+
+    assert_eq!(res, Err(42));
+    assert_eq!(attempts.get(), 6);
+    assert!(started.elapsed() < Duration::from_millis(100));
+}